@@ -1 +1,25 @@
-from .tokenizer import BPETokenizer
\ No newline at end of file
+from .tokenizer import BPETokenizer, StreamDecoder, TrainingStats, VocabSizeSuggestion
+from .builder import TokenizerBuilder
+from .config import (BinaryDatasetConfig, BlankInputPolicy, ChatTemplateConfig, CorpusFormat, EncodeLimits, EncodeStrategy,
+                     FrequencyMode, InvalidUtf8Policy, LanguageFilter, LengthFilter, PreTokenizer, PrintableFilter,
+                     SpecialTokenPolicy, TokenDtype, TokenizerConfig, TrainerConfig, VisualizationFormat, VocabIssueKind,
+                     VocabMergeStrategy, WordBoundary)
+from .corpus import guess_language
+from .special_tokens import SpecialTokensMap
+from .processors import TemplateProcessor
+from .encoding import Encoding, PackedBatch, Segment
+from .reports import (BinaryDatasetStats, ComparisonReport, CorpusEncodeStats, CoverageReport, DivergentText, EvalMetrics,
+                      MemoryReport, MergeConflict, MergeStep, MetricsSnapshot, NgramStats, ReferenceMismatch, ReferenceReport,
+                      RoundTripMismatch, ThroughputReport, TokenExplanation, ValidationReport, VocabIssue, VocabStats)
+from .metrics import InMemoryMetrics, Metrics
+from .stop import StopMatcher, StopHit, find_stop
+from .features import FeatureNotEnabled, enabled_features, is_feature_enabled
+from .parallel import get_num_threads, set_num_threads
+from .errors import (LucidError, LucidBlankInput, LucidCharacterNotFound, LucidDisallowedSpecialToken, LucidEncodeLimitExceeded,
+                     LucidFingerprintMismatch, LucidFrozen, LucidInvalidConfig, LucidInvalidMerge, LucidLanguageTagNotSet,
+                     LucidMissingDirectory, LucidNotTrained, LucidPostProcessorNotSet, LucidSpecialTokenNotFound,
+                     LucidSpecialTokenNotSet, LucidTokenGroupNotSet, LucidTokenIdNotFound, LucidUnsupportedVersion)
+from .segment import split_sentences
+from .bench import throughput
+from .server import make_server
+from .migrate import infer_config, migrate_artifact
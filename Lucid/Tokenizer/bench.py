@@ -0,0 +1,64 @@
+import time
+from typing import Callable
+
+from .reports import ThroughputReport
+from .tokenizer import BPETokenizer
+
+
+def best_time(func: Callable[[], object], repeats: int) -> float:
+    """
+    Run a function several times and keep the fastest wall-clock duration
+
+    Args:
+        func (callable) : The function to time
+        repeats (int) : Number of runs
+
+    Returns:
+        float : The fastest duration in seconds
+    """
+
+    best = float("inf")
+    for _ in range(max(repeats, 1)):
+        start = time.perf_counter()
+        func()
+        best = min(best, time.perf_counter() - start)
+    return max(best, 1e-9)
+
+
+def throughput(tokenizer: BPETokenizer, texts: list[str], repeats: int = 3,
+               train_vocab_size: int | None = None) -> ThroughputReport:
+    """
+    Measure encode, decode and optionally training speed on the given texts
+
+    No randomness is involved : the same texts are processed in every run and the
+    fastest run is reported, so results only vary with the machine's load.
+
+    Args:
+        tokenizer (BPETokenizer) : The tokenizer to benchmark
+        texts (list[str]) : The benchmark texts
+        repeats (int) : Number of runs per measurement
+        train_vocab_size (int or None) : Also time training a fresh tokenizer (same config) to this size
+
+    Returns:
+        ThroughputReport : Throughput in MB/s and tokens/s
+    """
+
+    report = ThroughputReport(num_bytes=sum(len(text.encode("utf-8")) for text in texts))
+    encoded = [tokenizer.encode(text) for text in texts]
+    report.num_tokens = sum(len(token_ids) for token_ids in encoded)
+    megabytes = report.num_bytes / 1e6
+
+    encode_time = best_time(lambda: [tokenizer.encode(text) for text in texts], repeats)
+    report.encode_mb_per_s = megabytes / encode_time
+    report.encode_tokens_per_s = report.num_tokens / encode_time
+
+    decode_time = best_time(lambda: [tokenizer.decode(token_ids) for token_ids in encoded], repeats)
+    report.decode_mb_per_s = megabytes / decode_time
+    report.decode_tokens_per_s = report.num_tokens / decode_time
+
+    if train_vocab_size is not None:
+        corpus = "\n".join(texts)
+        train_time = best_time(lambda: BPETokenizer(tokenizer.config).train(corpus, train_vocab_size), repeats)
+        report.train_mb_per_s = len(corpus.encode("utf-8")) / 1e6 / train_time
+
+    return report
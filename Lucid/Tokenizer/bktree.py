@@ -0,0 +1,102 @@
+def levenshtein(a: str, b: str) -> int:
+    """
+    Edit distance (insertions, deletions, substitutions) between two strings
+
+    Args:
+        a (str) : The first string
+        b (str) : The second string
+
+    Returns:
+        int : The distance
+    """
+
+    if len(a) < len(b):
+        a, b = b, a
+
+    previous = list(range(len(b) + 1))
+    for i, char_a in enumerate(a, start=1):
+        current = [i]
+        for j, char_b in enumerate(b, start=1):
+            current.append(min(previous[j] + 1, current[j - 1] + 1, previous[j - 1] + (char_a != char_b)))
+        previous = current
+    return previous[-1]
+
+
+class BKTree:
+
+    def __init__(self, items: dict[int, str] | None = None):
+        """
+        Burkhard-Keller tree over strings, for nearest-neighbour queries under the edit distance
+
+        Every node stores one string and the ids carrying it, and its children by their distance
+        to it; the triangle inequality prunes the subtrees that cannot hold close enough strings.
+
+        Args:
+            items (dict[int, str] or None) : Mapping of id to string to insert
+        """
+
+        # Node : [string, ids, {distance : child node}]
+        self.root = None
+        if items:
+            for item_id, text in sorted(items.items()):
+                self.insert(text, item_id)
+
+
+    def insert(self, text: str, item_id: int) -> None:
+        """
+        Insert a string into the tree
+
+        Args:
+            text (str) : The string
+            item_id (int) : The id it belongs to
+        """
+
+        if self.root is None:
+            self.root = [text, [item_id], {}]
+            return
+        node = self.root
+        while True:
+            distance = levenshtein(text, node[0])
+            if distance == 0:
+                node[1].append(item_id)
+                return
+            child = node[2].get(distance)
+            if child is None:
+                node[2][distance] = [text, [item_id], {}]
+                return
+            node = child
+
+
+    def nearest(self, text: str, k: int, max_distance: int | None = None) -> list[tuple[int, int]]:
+        """
+        Find the k ids whose strings are closest to a query
+
+        Args:
+            text (str) : The query
+            k (int) : Number of results
+            max_distance (int or None) : Ignore strings farther than this
+
+        Returns:
+            list[tuple[int, int]] : (distance, id) pairs, closest first, ties by id
+        """
+
+        if self.root is None or k <= 0:
+            return []
+
+        # Best results so far; the search radius shrinks to the k-th best distance once k are found
+        found = []
+        radius = max_distance
+        stack = [self.root]
+        while stack:
+            node = stack.pop()
+            distance = levenshtein(text, node[0])
+            if radius is None or distance <= radius:
+                found.extend((distance, item_id) for item_id in node[1])
+                found.sort()
+                del found[k:]
+                if len(found) == k:
+                    radius = found[-1][0] if radius is None else min(radius, found[-1][0])
+            for child_distance, child in node[2].items():
+                if radius is None or abs(child_distance - distance) <= radius:
+                    stack.append(child)
+        return found
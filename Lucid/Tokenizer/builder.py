@@ -0,0 +1,142 @@
+from dataclasses import fields
+from typing import Iterable
+
+from .config import (BlankInputPolicy, EncodeLimits, EncodeStrategy, PreTokenizer, TokenizerConfig, TrainerConfig,
+                     WordBoundary)
+from .errors import LucidInvalidConfig
+from .metrics import Metrics
+
+
+class TokenizerBuilder:
+
+    def __init__(self, tokenizer_cls):
+        """
+        Step-by-step construction of a tokenizer, validated as a whole by build() or train()
+
+        Obtained with BPETokenizer.builder(). Setters mirror the TokenizerConfig fields and the training
+        settings, and return the builder so calls chain :
+        BPETokenizer.builder().vocab_size(8000).special_tokens(["<|endoftext|>"]).byte_level(True).train(corpus)
+
+        Args:
+            tokenizer_cls (type) : The tokenizer class to build
+        """
+
+        self._tokenizer_cls = tokenizer_cls
+        # TokenizerConfig fields set so far
+        self._settings = {}
+        self._vocab_size = None
+        self._special_tokens = ["<|endoftext|>"]
+        self._trainer_config = None
+        self._encode_limits = None
+        self._metrics = None
+
+
+    def vocab_size(self, vocab_size: int) -> "TokenizerBuilder":
+        self._vocab_size = vocab_size
+        return self
+
+
+    def special_tokens(self, tokens: Iterable[str]) -> "TokenizerBuilder":
+        self._special_tokens = list(tokens)
+        return self
+
+
+    def pre_tokenizer(self, pre_tokenizer: PreTokenizer | str) -> "TokenizerBuilder":
+        return self._set("pre_tokenizer", PreTokenizer(pre_tokenizer))
+
+
+    def byte_level(self, byte_level: bool = True) -> "TokenizerBuilder":
+        return self._set("byte_level", byte_level)
+
+
+    def case_markers(self, case_markers: bool = True) -> "TokenizerBuilder":
+        return self._set("case_markers", case_markers)
+
+
+    def space_marker(self, space_marker: str | None) -> "TokenizerBuilder":
+        return self._set("space_marker", space_marker)
+
+
+    def split_digits(self, split_digits: bool = True) -> "TokenizerBuilder":
+        return self._set("split_digits", split_digits)
+
+
+    def word_boundary(self, word_boundary: WordBoundary | str) -> "TokenizerBuilder":
+        return self._set("word_boundary", WordBoundary(word_boundary))
+
+
+    def encode_strategy(self, encode_strategy: EncodeStrategy | str) -> "TokenizerBuilder":
+        return self._set("encode_strategy", EncodeStrategy(encode_strategy))
+
+
+    def tie_break_by_merge_rank(self, tie_break: bool = True) -> "TokenizerBuilder":
+        return self._set("tie_break_by_merge_rank", tie_break)
+
+
+    def blank_input(self, policy: BlankInputPolicy | str) -> "TokenizerBuilder":
+        return self._set("blank_input", BlankInputPolicy(policy))
+
+
+    def trainer_config(self, config: TrainerConfig) -> "TokenizerBuilder":
+        self._trainer_config = config
+        return self
+
+
+    def encode_limits(self, limits: EncodeLimits) -> "TokenizerBuilder":
+        self._encode_limits = limits
+        return self
+
+
+    def metrics(self, metrics: Metrics) -> "TokenizerBuilder":
+        self._metrics = metrics
+        return self
+
+
+    def config(self, config: TokenizerConfig) -> "TokenizerBuilder":
+        # Start from an existing config, later setters override its fields
+        self._settings = {field.name : getattr(config, field.name) for field in fields(TokenizerConfig)}
+        return self
+
+
+    def _set(self, name: str, value) -> "TokenizerBuilder":
+        self._settings[name] = value
+        return self
+
+
+    def build(self):
+        """
+        Validate the settings and create the (untrained) tokenizer
+
+        Returns:
+            BPETokenizer : The tokenizer, ready for train or load_vocab_and_merges
+        """
+
+        config = TokenizerConfig(**self._settings)
+        config.validate()
+        if self._vocab_size is not None and (not isinstance(self._vocab_size, int) or self._vocab_size <= 0):
+            raise LucidInvalidConfig(f"vocab_size must be a positive int, got {self._vocab_size!r}")
+        if not all(isinstance(token, str) and token for token in self._special_tokens):
+            raise LucidInvalidConfig(f"special_tokens must be non-empty strings, got {self._special_tokens!r}")
+
+        tokenizer = self._tokenizer_cls(config)
+        tokenizer.set_encode_limits(self._encode_limits)
+        tokenizer.set_metrics(self._metrics)
+        return tokenizer
+
+
+    def train(self, text: str):
+        """
+        Validate the settings, then create and train the tokenizer
+
+        Args:
+            text (str) : The training corpus
+
+        Returns:
+            BPETokenizer : The trained tokenizer
+        """
+
+        if self._vocab_size is None:
+            raise LucidInvalidConfig("vocab_size must be set before training")
+        tokenizer = self.build()
+        tokenizer.train(text, self._vocab_size, set(self._special_tokens), config=self._trainer_config)
+        return tokenizer
@@ -0,0 +1,244 @@
+import argparse
+import io
+import json
+import sys
+from dataclasses import asdict
+
+from .config import CorpusFormat, EncodeLimits
+from .corpus import iter_jsonl_field
+from .embedded import EMBEDDED_MAGIC
+from .fileio import COMPRESSED_SUFFIXES, open_compressed
+from .migrate import migrate_artifact
+from .server import make_server
+from .tokenizer import BPETokenizer
+
+# Path standing for standard input / output
+STDIO = "-"
+
+
+def open_input(path: str):
+    # UTF-8 text stream of a file (decompressed if .gz, .xz or .zst) or of stdin, whatever the locale
+    if path == STDIO:
+        return io.TextIOWrapper(sys.stdin.buffer, encoding="utf-8", newline=None)
+    if path.endswith(COMPRESSED_SUFFIXES):
+        return open_compressed(path)
+    return open(path, "r", encoding="utf-8")
+
+
+def open_output(path: str):
+    # Binary stream of a file or of stdout, left open for stdout
+    if path == STDIO:
+        return io.BufferedWriter(io.FileIO(sys.stdout.fileno(), "wb", closefd=False))
+    return open(path, "wb")
+
+
+def load_model(path: str) -> BPETokenizer:
+    # JSON artifact (BPETokenizer.save) or binary one (BPETokenizer.save_embedded), told apart by the magic
+    with open(path, "rb") as file:
+        data = file.read()
+    if data.startswith(EMBEDDED_MAGIC):
+        return BPETokenizer.from_embedded(data)
+    return BPETokenizer.from_json_str(data.decode("utf-8"))
+
+
+def allowed_special(value: str | None) -> set[str] | str | None:
+    # "all", "none" or a comma-separated list of tokens
+    if value is None or value in ("all", "none"):
+        return value
+    return set(value.split(","))
+
+
+def train(args: argparse.Namespace) -> int:
+    skipped = []
+    with open_input(args.corpus) as file:
+        corpus = file.read() if args.jsonl_field is None else "\n".join(iter_jsonl_field(file, args.jsonl_field, skipped))
+    if skipped:
+        print(f"Skipped {len(skipped)} malformed records (first at line {skipped[0]})", file=sys.stderr)
+    tokenizer = BPETokenizer()
+    tokenizer.train(corpus, args.vocab_size, set(args.special))
+
+    if args.output == STDIO:
+        sys.stdout.write(tokenizer.to_json_str() + "\n")
+    else:
+        tokenizer.save(args.output)
+        print(f"Trained {len(tokenizer.vocab)} tokens, saved to {args.output}", file=sys.stderr)
+    return 0
+
+
+def encode(args: argparse.Namespace) -> int:
+    tokenizer = load_model(args.model)
+    with open_input(args.input) as source, open_output(args.output) as output:
+        tokenizer.encode_corpus(source, output, args.format, allowed_special(args.allowed_special))
+    return 0
+
+
+def validate(args: argparse.Namespace) -> int:
+    tokenizer = load_model(args.tokenizer)
+    with open_input(args.corpus) as file:
+        report = tokenizer.validate_roundtrip(file, max_mismatches=args.max_mismatches)
+
+    if args.json:
+        print(json.dumps(asdict(report), ensure_ascii=False, indent=4))
+    else:
+        for mismatch in report.mismatches:
+            print(f"line {mismatch.line_number} (char {mismatch.offset}, byte {mismatch.byte_offset}) :")
+            for change in mismatch.diff:
+                print(f"    {change}")
+        print(f"{report.num_lines - report.num_mismatches}/{report.num_lines} lines round-trip losslessly")
+    return 0 if report.ok else 1
+
+
+def explain(args: argparse.Namespace) -> int:
+    tokenizer = load_model(args.model)
+    explanations = tokenizer.explain(args.text, allowed_special(args.allowed_special))
+
+    if args.json:
+        print(json.dumps([asdict(explanation) for explanation in explanations], ensure_ascii=False, indent=4))
+        return 0
+    for explanation in explanations:
+        start, end = explanation.offsets
+        rank = "special" if explanation.special else ("base" if explanation.rank is None else f"rank {explanation.rank}")
+        print(f"{explanation.token_id:>8}  {explanation.surface!r:<20} [{start}:{end}]  {rank}")
+        for merge in explanation.merge_chain:
+            print(f"{'':>10}{merge}")
+    return 0
+
+
+def compare(args: argparse.Namespace) -> int:
+    tokenizer_a, tokenizer_b = load_model(args.a), load_model(args.b)
+    with open_input(args.corpus) as file:
+        report = tokenizer_a.compare(tokenizer_b, file, allowed_special(args.allowed_special), args.examples)
+
+    if args.json:
+        print(json.dumps(asdict(report), ensure_ascii=False, indent=4))
+        return 0
+    print(f"{'':<16}{'a':>14}{'b':>14}")
+    print(f"{'vocab size':<16}{report.vocab_size_a:>14}{report.vocab_size_b:>14}")
+    print(f"{'tokens':<16}{report.num_tokens_a:>14}{report.num_tokens_b:>14}")
+    print(f"{'tokens/byte':<16}{report.tokens_per_byte_a:>14.4f}{report.tokens_per_byte_b:>14.4f}")
+    print(f"{report.shared_tokens} shared tokens (overlap {report.vocab_overlap:.2%}), "
+          f"{report.num_lines} lines compared, {report.num_failed} failed")
+    for example in report.examples:
+        print(f"\nline {example.line_number} (divergence {example.divergence:.2f}) :")
+        print(f"    a : {' | '.join(repr(piece) for piece in example.pieces_a)}")
+        print(f"    b : {' | '.join(repr(piece) for piece in example.pieces_b)}")
+    return 0
+
+
+def serve(args: argparse.Namespace) -> int:
+    tokenizer = load_model(args.model)
+    tokenizer.set_encode_limits(EncodeLimits(args.max_input_bytes, args.max_output_tokens))
+    server = make_server(tokenizer, args.host, args.port)
+    host, port = server.server_address[:2]
+    print(f"Serving {args.model} on http://{host}:{port}", file=sys.stderr)
+    try:
+        server.serve_forever()
+    except KeyboardInterrupt:
+        pass
+    finally:
+        server.server_close()
+    return 0
+
+
+def migrate(args: argparse.Namespace) -> int:
+    tokenizer = migrate_artifact(args.vocab, args.merges, args.output)
+    config = tokenizer.config.to_dict()
+    print(f"Migrated {len(tokenizer.vocab)} tokens and {len(tokenizer.bpe_merges)} merges to {args.output}", file=sys.stderr)
+    print(f"Inferred config : {json.dumps(config, ensure_ascii=False)}", file=sys.stderr)
+    return 0
+
+
+def embed(args: argparse.Namespace) -> int:
+    tokenizer = load_model(args.model)
+    tokenizer.save_embedded(args.output)
+    print(f"Wrote {len(tokenizer.vocab)} tokens and {len(tokenizer.bpe_merges)} merges to {args.output}", file=sys.stderr)
+    return 0
+
+
+def build_parser() -> argparse.ArgumentParser:
+    parser = argparse.ArgumentParser(prog="lucid", description="Lucid tokenizer command line tools")
+    commands = parser.add_subparsers(dest="command", required=True)
+
+    validate_parser = commands.add_parser("validate", help="Check that every line of a corpus survives encode + decode")
+    validate_parser.add_argument("tokenizer", help="Path of a tokenizer saved with BPETokenizer.save")
+    validate_parser.add_argument("corpus", help="Path of a UTF-8 text corpus (optionally .gz, .xz or .zst), checked line by line")
+    validate_parser.add_argument("--max-mismatches", type=int, default=100, help="Maximum number of mismatches reported")
+    validate_parser.add_argument("--json", action="store_true", help="Print the report as JSON")
+    validate_parser.set_defaults(func=validate)
+
+    train_parser = commands.add_parser("train", help="Train a tokenizer on a UTF-8 corpus")
+    train_parser.add_argument("corpus", help="Path of the training corpus (optionally .gz, .xz or .zst), - for stdin")
+    train_parser.add_argument("--vocab-size", type=int, required=True, help="Target vocabulary size")
+    train_parser.add_argument("--output", "-o", required=True, help="Path of the saved tokenizer, - for stdout")
+    train_parser.add_argument("--special", action="append", default=["<|endoftext|>"],
+                              help="Special token added to the vocabulary (repeatable)")
+    train_parser.add_argument("--jsonl-field",
+                              help='Read the corpus as JSON Lines and train on this field of every record (e.g. "text")')
+    train_parser.set_defaults(func=train)
+
+    encode_parser = commands.add_parser("encode", help="Encode a corpus line by line")
+    encode_parser.add_argument("input", nargs="?", default=STDIO,
+                               help="Path of the UTF-8 input (optionally .gz, .xz or .zst), - for stdin (default)")
+    encode_parser.add_argument("--model", "-m", required=True, help="Path of a tokenizer saved with BPETokenizer.save")
+    encode_parser.add_argument("--output", "-o", default=STDIO, help="Path of the output, - for stdout (default)")
+    encode_parser.add_argument("--format", choices=[format.value for format in CorpusFormat], default=CorpusFormat.JSONL.value,
+                               help="jsonl : a JSON array per line, text : space-separated ids per line, bin : raw ids")
+    encode_parser.add_argument("--allowed-special", help="Special tokens to pass through : all, none or a comma-separated list")
+    encode_parser.set_defaults(func=encode)
+
+    explain_parser = commands.add_parser("explain", help="Show how each token of a text was produced")
+    explain_parser.add_argument("text", help="The text to explain")
+    explain_parser.add_argument("--model", "-m", required=True, help="Path of a tokenizer saved with BPETokenizer.save")
+    explain_parser.add_argument("--allowed-special", help="Special tokens to pass through : all, none or a comma-separated list")
+    explain_parser.add_argument("--json", action="store_true", help="Print the explanations as JSON")
+    explain_parser.set_defaults(func=explain)
+
+    compare_parser = commands.add_parser("compare", help="Compare the compression and segmentation of two tokenizers on a corpus")
+    compare_parser.add_argument("--a", required=True, help="Path of the first tokenizer (e.g. the one in production)")
+    compare_parser.add_argument("--b", required=True, help="Path of the second tokenizer (e.g. the candidate)")
+    compare_parser.add_argument("--corpus", required=True,
+                                help="Path of a UTF-8 corpus (optionally .gz, .xz or .zst), compared line by line, - for stdin")
+    compare_parser.add_argument("--examples", type=int, default=5, help="Number of most divergent lines shown")
+    compare_parser.add_argument("--allowed-special", help="Special tokens to pass through : all, none or a comma-separated list")
+    compare_parser.add_argument("--json", action="store_true", help="Print the report as JSON")
+    compare_parser.set_defaults(func=compare)
+
+    serve_parser = commands.add_parser("serve", help="Serve a tokenizer over HTTP (/encode, /decode, /count_tokens, /info)")
+    serve_parser.add_argument("--model", "-m", required=True, help="Path of a tokenizer saved with BPETokenizer.save")
+    serve_parser.add_argument("--host", default="127.0.0.1", help="Interface to bind (default: localhost only)")
+    serve_parser.add_argument("--port", type=int, default=8000, help="Port to bind")
+    serve_parser.add_argument("--max-input-bytes", type=int, help="Reject texts larger than this many UTF-8 bytes")
+    serve_parser.add_argument("--max-output-tokens", type=int, help="Reject texts encoding to more than this many tokens")
+    serve_parser.set_defaults(func=serve)
+
+    migrate_parser = commands.add_parser("migrate", help="Convert a vocab + merges artifact pair into a single-file tokenizer")
+    migrate_parser.add_argument("vocab", help="Path of the vocabulary JSON written by save_vocab_and_merges")
+    migrate_parser.add_argument("merges", help="Path of the merges JSON written by save_vocab_and_merges")
+    migrate_parser.add_argument("--output", "-o", required=True, help="Path of the migrated tokenizer")
+    migrate_parser.set_defaults(func=migrate)
+
+    embed_parser = commands.add_parser("embed", help="Convert a tokenizer to the binary layout of BPETokenizer.from_embedded")
+    embed_parser.add_argument("model", help="Path of a tokenizer saved with BPETokenizer.save")
+    embed_parser.add_argument("--output", "-o", required=True, help="Path of the binary artifact")
+    embed_parser.set_defaults(func=embed)
+
+    return parser
+
+
+def main(argv: list[str] | None = None) -> int:
+    """
+    Entry point of the `lucid` command
+
+    Args:
+        argv (list[str] or None) : Command line arguments, defaults to sys.argv[1:]
+
+    Returns:
+        int : The process exit code
+    """
+
+    args = build_parser().parse_args(argv)
+    return args.func(args)
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,343 @@
+from dataclasses import asdict, dataclass, field
+from enum import Enum
+
+from .errors import LucidInvalidConfig, LucidUnsupportedVersion
+
+# Version of the TokenizerConfig layout written into saved artifacts
+TOKENIZER_CONFIG_VERSION = 1
+
+# Serialized default of the TokenizerConfig fields added after fingerprints were introduced : left out of the
+# fingerprint while at their default, so tokenizers saved before the field existed keep their fingerprint
+FINGERPRINT_DEFAULTED_FIELDS = {"blank_input" : "encode"}
+
+
+class PreTokenizer(str, Enum):
+    # Split on whitespace, collapsing runs of spaces and tabs
+    WHITESPACE = "whitespace"
+    # Keep indentation and newlines, split identifiers from operators (lossless)
+    CODE = "code"
+
+
+class WordBoundary(str, Enum):
+    # GPT-style : a marker starts every word preceded by a space ("Ġword")
+    START = "start"
+    # Classic BPE (subword-nmt, fairseq) : the last symbol of every word ends with "</w>" ("word</w>")
+    END = "end"
+
+
+class EncodeStrategy(str, Enum):
+    # Apply the learned merges (standard BPE)
+    MERGES = "merges"
+    # Greedily take the longest vocabulary entry at each position (WordPiece-style, no merges needed)
+    LONGEST_MATCH = "longest_match"
+    # Dynamic programming over the vocabulary for the fewest tokens, ignoring merge order
+    OPTIMAL = "optimal"
+
+
+class BlankInputPolicy(str, Enum):
+    # Encode like any other text : the whitespace pre-tokenizer drops spaces and keeps newlines, the code one keeps all
+    ENCODE = "encode"
+    # Empty and whitespace-only texts encode to no tokens
+    EMPTY = "empty"
+    # Whitespace-only texts encode to the single space token, empty texts to no tokens
+    SPACE = "space"
+    # Empty and whitespace-only texts raise LucidBlankInput
+    ERROR = "error"
+
+
+class SpecialTokenPolicy(str, Enum):
+    # Raise LucidDisallowedSpecialToken when the text contains a special token
+    RAISE = "raise"
+    # Encode special token strings as ordinary text
+    PLAIN = "plain"
+    # Escape special token strings (see BPETokenizer.escape_special_tokens) before encoding them as ordinary text
+    ESCAPE = "escape"
+
+
+class InvalidUtf8Policy(str, Enum):
+    # Replace every invalid byte sequence (and encoded lone surrogate) with U+FFFD before encoding
+    REPLACE = "replace"
+    # Keep the raw bytes and encode them as byte tokens (requires byte-level mode), losing nothing
+    BYTE_FALLBACK = "byte_fallback"
+
+
+class VocabMergeStrategy(str, Enum):
+    # Keep every merge of the first tokenizer ahead of the second's
+    APPEND = "append"
+    # Order merges by their relative rank in their own table, so both domains keep their priorities
+    INTERLEAVE = "interleave"
+
+
+class FrequencyMode(str, Enum):
+    # The most frequent pair, as merged by BPE training
+    MOST = "most"
+    # The least frequent pair (deprecated : trim rare tokens with BPETokenizer.trim_vocab instead)
+    LEAST = "least"
+
+
+class CorpusFormat(str, Enum):
+    # One JSON array of token ids per input line
+    JSONL = "jsonl"
+    # One line of space-separated token ids per input line
+    TEXT = "text"
+    # Flat little-endian token ids, as written by tokenize_file_to_bin
+    BIN = "bin"
+
+
+class VisualizationFormat(str, Enum):
+    # HTML with a colored span per token and its id on hover, displayed inline by notebooks
+    HTML = "html"
+    # ANSI escape codes coloring the background of each token, for terminals
+    ANSI = "ansi"
+
+
+class VocabIssueKind(str, Enum):
+    # Tokens equal up to case ("Hello" and "hello")
+    CASE_VARIANT = "case_variant"
+    # Tokens equal after NFKC normalization (e.g. "ﬁ" and "fi", full-width and ASCII digits)
+    NFKC_EQUIVALENT = "nfkc_equivalent"
+    # Tokens equal up to their leading or trailing spaces ("hello" and " hello")
+    SPACE_MARKER_VARIANT = "space_marker_variant"
+
+
+class TokenDtype(str, Enum):
+    # Unsigned 16-bit ids, enough for vocabularies of up to 65536 tokens
+    UINT16 = "uint16"
+    # Unsigned 32-bit ids
+    UINT32 = "uint32"
+
+
+@dataclass
+class LengthFilter:
+    # Lines shorter than this many characters (after stripping) are dropped
+    min_chars: int = 0
+    # Lines longer than this are dropped, None for no limit
+    max_chars: int | None = None
+    # Filter kind, used to rebuild the filter from a checkpoint
+    kind: str = "length"
+
+
+@dataclass
+class PrintableFilter:
+    # Lines whose share of printable characters (whitespace counts as printable) is below this are dropped
+    min_ratio: float = 0.9
+    # Filter kind, used to rebuild the filter from a checkpoint
+    kind: str = "printable"
+
+
+@dataclass
+class LanguageFilter:
+    # Languages to keep, as codes returned by corpus.guess_language (e.g. ["en", "fr"])
+    languages: list[str] = field(default_factory=lambda: ["en"])
+    # Keep lines whose language cannot be guessed (e.g. too short or only digits)
+    keep_unknown: bool = False
+    # Filter kind, used to rebuild the filter from a checkpoint
+    kind: str = "language"
+
+
+# Filter class of each kind
+FILTER_KINDS = {"length" : LengthFilter, "printable" : PrintableFilter, "language" : LanguageFilter}
+
+CorpusFilter = LengthFilter | PrintableFilter | LanguageFilter
+
+
+def filter_from_dict(data: dict) -> CorpusFilter:
+    """
+    Rebuild a corpus filter from its dictionary form (as written by dataclasses.asdict)
+
+    Args:
+        data (dict) : The filter fields, including its kind
+
+    Returns:
+        CorpusFilter : The filter
+    """
+
+    if data.get("kind") not in FILTER_KINDS:
+        raise LucidInvalidConfig(f"Unknown corpus filter kind {data.get('kind')!r}, expected one of {sorted(FILTER_KINDS)}")
+    return FILTER_KINDS[data["kind"]](**data)
+
+
+@dataclass
+class TrainerConfig:
+    # Skip near-duplicate lines/paragraphs so boilerplate doesn't dominate merge statistics
+    dedupe: bool = False
+    # Unit compared when deduplicating : "line" or "paragraph"
+    dedupe_unit: str = "line"
+    # Regex patterns; a merge whose resulting token matches any of them is never learned (e.g. r"\d\D|\D\d")
+    never_merge: list[str] = field(default_factory=list)
+    # Tokens (in vocabulary form, spaces written as the space marker) guaranteed to exist as single tokens
+    protected_tokens: list[str] = field(default_factory=list)
+    # Merged tokens longer than this are never learned, None for no limit (protected tokens are exempt)
+    max_token_length: int | None = None
+    # Unit of max_token_length : "chars" or "bytes" (UTF-8)
+    max_token_length_unit: str = "chars"
+    # Special tokens assigned fixed ids, e.g. {"<|pad|>": 0, "<|bos|>": 1, "<|eos|>": 2}
+    pinned_special_tokens: dict[str, int] = field(default_factory=dict)
+    # Half-open [start, end) id ranges kept free for special tokens added later with add_special_tokens
+    reserved_id_ranges: list[tuple[int, int]] = field(default_factory=list)
+    # File written by save_checkpoint during training, None to disable periodic checkpoints
+    checkpoint_path: str | None = None
+    # Number of merges between two periodic checkpoints
+    checkpoint_every: int = 1000
+    # Line filters applied to the raw corpus before deduplication; a line is kept only if every filter accepts it
+    filters: list[CorpusFilter] = field(default_factory=list)
+    # Regex splitting the corpus into documents (e.g. r"\n\n+" or re.escape("<|endoftext|>")); words are counted
+    # per document so no merge statistics span two documents. None keeps the corpus as a single document
+    document_separator: str | None = None
+    # Special token standing for a document boundary : added to the vocabulary and assigned the eos role
+    document_eos: str | None = None
+    # Fraction of the corpus characters the initial vocabulary must cover (e.g. 0.9995, as in SentencePiece) : the
+    # rarest characters beyond it are left out and encode to the unk token, which must then be set. 1.0 keeps every character
+    character_coverage: float = 1.0
+    # Count the character and word n-grams of the corpus (after filters and deduplication) into TrainingStats.ngrams
+    collect_ngrams: bool = False
+    # Orders (n) of the collected n-grams
+    ngram_orders: list[int] = field(default_factory=lambda: [1, 2, 3])
+    # Number of most frequent n-grams kept for each kind (character, word) and order
+    ngram_top_k: int = 1000
+    # Sidecar JSON file the collected n-grams are written to, None to only return them
+    ngram_path: str | None = None
+    # (minimum, maximum) share of the learned merges attributed to each language, e.g. {"en" : (0.0, 0.5), "hi" : (0.2, 1.0)},
+    # for train_from_shards with languages. A merge is attributed to the language contributing most of its pair count
+    language_budgets: dict[str, tuple[float, float]] = field(default_factory=dict)
+    # Processes counting the training words (train : chunks of the corpus, train_from_shards : shards), None for
+    # get_num_threads() (1 unless set, see set_num_threads)
+    num_workers: int | None = None
+
+
+    def __post_init__(self):
+        # Filters restored from a checkpoint are plain dictionaries
+        self.filters = [filter_from_dict(item) if isinstance(item, dict) else item for item in self.filters]
+        if self.num_workers is not None and self.num_workers < 1:
+            raise ValueError("num_workers must be at least 1")
+
+
+@dataclass
+class TokenizerConfig:
+    # Lowercase input and record casing with <|cap|> / <|upper|> markers restored by decode
+    case_markers: bool = False
+    # Character standing for a preceding space : "Ġ" (GPT-2), "▁" (SentencePiece) or None to keep literal spaces
+    space_marker: str | None = "Ġ"
+    # Make every digit a separate token during both training and encoding
+    split_digits: bool = False
+    # How text is split into words before BPE : PreTokenizer.WHITESPACE or PreTokenizer.CODE
+    pre_tokenizer: PreTokenizer = PreTokenizer.WHITESPACE
+    # Operate on UTF-8 bytes (each byte is the character chr(byte)) so any input, including invalid UTF-8, is encodable
+    byte_level: bool = False
+    # Where word boundaries are marked : WordBoundary.START (space marker) or WordBoundary.END ("</w>" suffix)
+    word_boundary: WordBoundary = WordBoundary.START
+    # How a pre-token is split into tokens : EncodeStrategy.MERGES (learned merges), EncodeStrategy.LONGEST_MATCH
+    # (greedy trie lookup) or EncodeStrategy.OPTIMAL (fewest tokens)
+    encode_strategy: EncodeStrategy = EncodeStrategy.MERGES
+    # With EncodeStrategy.OPTIMAL, break ties between equally short segmentations by the lowest total merge rank
+    # instead of the longest first token
+    tie_break_by_merge_rank: bool = False
+    # Result of encoding an empty or whitespace-only (spaces, tabs, newlines) text
+    blank_input: BlankInputPolicy = BlankInputPolicy.ENCODE
+
+
+    def validate(self) -> None:
+        """
+        Check that the settings are well-formed and compatible with each other
+        """
+
+        for name in ("case_markers", "split_digits", "byte_level", "tie_break_by_merge_rank"):
+            if not isinstance(getattr(self, name), bool):
+                raise LucidInvalidConfig(f"{name} must be a bool, got {getattr(self, name)!r}")
+        if self.space_marker is not None and (not isinstance(self.space_marker, str) or len(self.space_marker) != 1):
+            raise LucidInvalidConfig(f"space_marker must be a single character or None, got {self.space_marker!r}")
+        if not isinstance(self.pre_tokenizer, PreTokenizer):
+            raise LucidInvalidConfig(f"pre_tokenizer must be a PreTokenizer, got {self.pre_tokenizer!r}")
+        if self.byte_level and self.case_markers:
+            raise LucidInvalidConfig("case_markers cannot be combined with byte_level")
+        if not isinstance(self.encode_strategy, EncodeStrategy):
+            raise LucidInvalidConfig(f"encode_strategy must be an EncodeStrategy, got {self.encode_strategy!r}")
+        if not isinstance(self.blank_input, BlankInputPolicy):
+            raise LucidInvalidConfig(f"blank_input must be a BlankInputPolicy, got {self.blank_input!r}")
+        if not isinstance(self.word_boundary, WordBoundary):
+            raise LucidInvalidConfig(f"word_boundary must be a WordBoundary, got {self.word_boundary!r}")
+        if self.word_boundary == WordBoundary.END and (self.pre_tokenizer != PreTokenizer.WHITESPACE or self.case_markers):
+            raise LucidInvalidConfig("End-of-word marking requires the whitespace pre-tokenizer and no case markers")
+        if self.word_boundary == WordBoundary.END and self.blank_input == BlankInputPolicy.SPACE and self.space_marker is not None:
+            raise LucidInvalidConfig("blank_input=SPACE with end-of-word marking requires space_marker=None (a literal space token)")
+
+
+    def to_dict(self) -> dict:
+        data = {"version" : TOKENIZER_CONFIG_VERSION}
+        data.update(asdict(self))
+        data["pre_tokenizer"] = self.pre_tokenizer.value
+        data["word_boundary"] = self.word_boundary.value
+        data["encode_strategy"] = self.encode_strategy.value
+        data["blank_input"] = self.blank_input.value
+        return data
+
+
+    @classmethod
+    def from_dict(cls, data: dict) -> "TokenizerConfig":
+        """
+        Rebuild and validate a config written by to_dict
+
+        Args:
+            data (dict) : The serialized config
+
+        Returns:
+            TokenizerConfig : The config
+        """
+
+        version = data.get("version", TOKENIZER_CONFIG_VERSION)
+        if not isinstance(version, int) or isinstance(version, bool) or version > TOKENIZER_CONFIG_VERSION:
+            raise LucidUnsupportedVersion(version, TOKENIZER_CONFIG_VERSION)
+
+        unknown = sorted(set(data) - set(cls.__dataclass_fields__) - {"version"})
+        if unknown:
+            raise LucidInvalidConfig(f"Unknown tokenizer config fields : {unknown}")
+
+        values = {key : value for key, value in data.items() if key != "version"}
+        for name, enum in (("pre_tokenizer", PreTokenizer), ("word_boundary", WordBoundary), ("encode_strategy", EncodeStrategy),
+                           ("blank_input", BlankInputPolicy)):
+            if name in values:
+                try:
+                    values[name] = enum(values[name])
+                except ValueError:
+                    raise LucidInvalidConfig(f"Unknown {name} {values[name]!r}. "
+                                             f"Choose one of {[member.value for member in enum]}") from None
+
+        config = cls(**values)
+        config.validate()
+        return config
+
+
+@dataclass
+class ChatTemplateConfig:
+    # Special token opening each message, followed by the role name
+    role_start: str = "<|im_start|>"
+    # Special token closing each message
+    role_end: str = "<|im_end|>"
+    # Text placed after the role name and after each closed message
+    separator: str = "\n"
+    # Open an assistant message at the end so the model continues from it
+    add_generation_prompt: bool = False
+    # Role used for the generation prompt
+    assistant_role: str = "assistant"
+
+
+@dataclass
+class BinaryDatasetConfig:
+    # How the input file is split into documents : "line", "paragraph" or "file"
+    document_unit: str = "line"
+    # Append the eos special token after every document
+    append_eos: bool = True
+    # Special tokens allowed to pass through when encoding documents
+    allowed_special: set[str] | None = None
+    # Id type of the output file, None for the smallest one fitting the vocabulary
+    dtype: TokenDtype | None = None
+    # Number of documents encoded before each write to disk
+    batch_documents: int = 1024
+
+
+@dataclass
+class EncodeLimits:
+    # Largest accepted input, in UTF-8 bytes, None for no limit
+    max_input_bytes: int | None = None
+    # Largest number of tokens an encode may produce, None for no limit
+    max_output_tokens: int | None = None
@@ -0,0 +1,248 @@
+from collections import Counter
+from typing import Iterable, Iterator
+import hashlib
+import json
+import re
+import unicodedata
+
+from .config import CorpusFilter, LanguageFilter, LengthFilter, PrintableFilter
+
+# Frequent function words of the Latin-script languages told apart by guess_language
+STOPWORDS = {
+    "en" : frozenset({"the", "and", "of", "to", "is", "in", "that", "it", "was", "for", "with", "are", "this", "be", "not"}),
+    "fr" : frozenset({"le", "la", "les", "et", "des", "est", "une", "dans", "que", "pour", "pas", "du", "qui", "sur", "au"}),
+    "de" : frozenset({"der", "die", "und", "das", "ist", "nicht", "ein", "eine", "ich", "mit", "den", "zu", "von", "sich", "auf"}),
+    "es" : frozenset({"el", "los", "las", "y", "que", "es", "una", "por", "con", "para", "del", "se", "como", "pero", "su"}),
+    "it" : frozenset({"il", "che", "di", "e", "non", "una", "per", "sono", "della", "con", "gli", "anche", "del", "nel", "questo"}),
+    "pt" : frozenset({"o", "os", "que", "não", "uma", "com", "para", "por", "do", "da", "em", "é", "mais", "como", "seu"}),
+    "nl" : frozenset({"de", "het", "een", "en", "van", "niet", "is", "dat", "op", "te", "zijn", "met", "voor", "ook", "maar"}),
+}
+
+# Language assumed for text written mostly in a non-Latin script (first word of the Unicode character name)
+SCRIPT_LANGUAGES = {
+    "CYRILLIC" : "ru", "GREEK" : "el", "ARABIC" : "ar", "HEBREW" : "he", "DEVANAGARI" : "hi",
+    "HANGUL" : "ko", "HIRAGANA" : "ja", "KATAKANA" : "ja", "CJK" : "zh", "THAI" : "th",
+}
+
+WORD_PATTERN = re.compile(r"[^\W\d_]+")
+
+
+def dedupe_text(text: str, unit: str = "line") -> tuple[str, int]:
+    """
+    Remove near-duplicate lines or paragraphs from a corpus
+
+    Two units are considered duplicates when they are equal after lowercasing
+    and collapsing whitespace. Blank units are always kept.
+
+    Args:
+        text (str) : The corpus text
+        unit (str) : "line" or "paragraph"
+
+    Returns:
+        tuple[str, int] : The deduplicated text and the number of units skipped
+    """
+
+    if unit == "line":
+        separator = "\n"
+    elif unit == "paragraph":
+        separator = "\n\n"
+    else:
+        raise ValueError("Invalid dedupe unit. Choose 'line' or 'paragraph'")
+
+    seen = set()
+    kept = []
+    skipped = 0
+    for chunk in text.split(separator):
+        normalized = " ".join(chunk.lower().split())
+        if not normalized:
+            kept.append(chunk)
+            continue
+        digest = hashlib.blake2b(normalized.encode("utf-8"), digest_size=16).digest()
+        if digest in seen:
+            skipped += 1
+            continue
+        seen.add(digest)
+        kept.append(chunk)
+
+    return separator.join(kept), skipped
+
+
+def iter_documents(lines: Iterable[str], unit: str = "line") -> Iterator[str]:
+    """
+    Group a stream of lines into documents without reading the whole corpus
+
+    Args:
+        lines (iterable of str) : The corpus lines, with or without their line endings
+        unit (str) : "line" (one document per line), "paragraph" (documents separated by blank lines) or "file"
+
+    Yields:
+        str : Each non-empty document, without its trailing newline
+    """
+
+    if unit not in ("line", "paragraph", "file"):
+        raise ValueError("Invalid document unit. Choose 'line', 'paragraph' or 'file'")
+
+    buffer = []
+    for line in lines:
+        line = line.rstrip("\n")
+        if unit == "line":
+            if line:
+                yield line
+        elif unit == "paragraph" and not line.strip():
+            if buffer:
+                yield "\n".join(buffer)
+            buffer = []
+        else:
+            buffer.append(line)
+
+    if buffer:
+        yield "\n".join(buffer)
+
+
+def iter_jsonl_field(lines: Iterable[str], field: str = "text", skipped: list[int] | None = None) -> Iterator[str]:
+    """
+    Extract one string field from every record of a JSON Lines stream (e.g. a dataset dump)
+
+    Records that are not valid JSON objects, lack the field or hold a non-string value are
+    skipped rather than aborting a long read; their line numbers are reported through skipped.
+
+    Args:
+        lines (iterable of str) : The JSON Lines, one record per line
+        field (str) : Key of the text field, dotted for nested objects (e.g. "meta.text")
+        skipped (list[int] or None) : If given, the 1-based numbers of the skipped lines are appended to it
+
+    Yields:
+        str : The field of each well-formed record
+    """
+
+    keys = field.split(".")
+    for line_number, line in enumerate(lines, start=1):
+        if not line.strip():
+            continue
+        try:
+            value = json.loads(line)
+            for key in keys:
+                value = value[key]
+        except (ValueError, KeyError, TypeError, IndexError):
+            value = None
+        if isinstance(value, str):
+            yield value
+        elif skipped is not None:
+            skipped.append(line_number)
+
+
+def count_ngrams(text: str, orders: Iterable[int],
+                 counts: dict[tuple[str, int], Counter] | None = None) -> dict[tuple[str, int], Counter]:
+    """
+    Count the character and word n-grams of a text, line by line (no n-gram spans a newline)
+
+    Args:
+        text (str) : The corpus text
+        orders (iterable of int) : The n-gram orders to count
+        counts (dict or None) : Counts to update, e.g. those of the previous shards
+
+    Returns:
+        dict[tuple[str, int], Counter] : Counter of the n-grams of each ("char" or "word", n), words joined by a space
+    """
+
+    orders = sorted(set(orders))
+    if any(n < 1 for n in orders):
+        raise ValueError("n-gram orders must be positive")
+    counts = {} if counts is None else counts
+    for n in orders:
+        counts.setdefault(("char", n), Counter())
+        counts.setdefault(("word", n), Counter())
+
+    for line in text.split("\n"):
+        words = line.split()
+        for n in orders:
+            counts[("char", n)].update(line[i:i + n] for i in range(len(line) - n + 1))
+            counts[("word", n)].update(" ".join(words[i:i + n]) for i in range(len(words) - n + 1))
+    return counts
+
+
+def guess_language(text: str) -> str | None:
+    """
+    Guess the language of a short text with a script and stopword heuristic
+
+    Text mostly written in a non-Latin script is attributed to that script's main language
+    (Japanese whenever kana appear among CJK characters). Latin-script text is attributed to
+    the language whose function words (STOPWORDS) occur most often.
+
+    Args:
+        text (str) : The text, typically one line
+
+    Returns:
+        str or None : A language code such as "en" or "ru", None if the text gives no clue
+    """
+
+    scripts = {}
+    for char in text:
+        if char < "\x80":
+            if char.isalpha():
+                scripts["LATIN"] = scripts.get("LATIN", 0) + 1
+        elif char.isalpha():
+            script = unicodedata.name(char, "").split(" ")[0]
+            scripts[script] = scripts.get(script, 0) + 1
+    if not scripts:
+        return None
+
+    script = max(scripts, key=scripts.get)
+    if script != "LATIN":
+        if script == "CJK" and (scripts.get("HIRAGANA") or scripts.get("KATAKANA")):
+            return "ja"
+        return SCRIPT_LANGUAGES.get(script)
+
+    words = WORD_PATTERN.findall(text.lower())
+    hits = {language : sum(word in stopwords for word in words) for language, stopwords in STOPWORDS.items()}
+    language = max(hits, key=hits.get)
+    return language if hits[language] > 0 else None
+
+
+def accepts_line(line: str, corpus_filter: CorpusFilter) -> bool:
+    """
+    Check whether a corpus line passes a filter
+
+    Args:
+        line (str) : The line, without its line ending
+        corpus_filter (CorpusFilter) : The filter to apply
+
+    Returns:
+        bool : True if the line is kept
+    """
+
+    if isinstance(corpus_filter, LengthFilter):
+        length = len(line.strip())
+        return length >= corpus_filter.min_chars and (corpus_filter.max_chars is None or length <= corpus_filter.max_chars)
+    if isinstance(corpus_filter, PrintableFilter):
+        printable = sum(char.isprintable() or char.isspace() for char in line)
+        return printable >= corpus_filter.min_ratio * len(line)
+    if isinstance(corpus_filter, LanguageFilter):
+        language = guess_language(line)
+        return corpus_filter.keep_unknown if language is None else language in corpus_filter.languages
+    raise TypeError(f"Unknown corpus filter : {corpus_filter!r}")
+
+
+def filter_text(text: str, filters: list[CorpusFilter]) -> tuple[str, int]:
+    """
+    Drop the corpus lines rejected by any of the filters
+
+    Blank lines are always kept so paragraph boundaries survive.
+
+    Args:
+        text (str) : The corpus text
+        filters (list[CorpusFilter]) : The filters, all of which must accept a line for it to be kept
+
+    Returns:
+        tuple[str, int] : The filtered text and the number of lines dropped
+    """
+
+    kept = []
+    skipped = 0
+    for line in text.split("\n"):
+        if line.strip() and not all(accepts_line(line, corpus_filter) for corpus_filter in filters):
+            skipped += 1
+            continue
+        kept.append(line)
+
+    return "\n".join(kept), skipped
@@ -0,0 +1,93 @@
+import json
+import struct
+import sys
+
+# File magic and format version written by pack_artifact
+EMBEDDED_MAGIC = b"LUCB"
+EMBEDDED_VERSION = 1
+
+# Header : magic, version, number of tokens, number of merges, metadata length, token text length
+HEADER = struct.Struct("<4sIIIII")
+
+
+def _uint32_array(values: list[int]) -> bytes:
+    return struct.pack(f"<{len(values)}I", *values)
+
+
+def _uint32_view(data: memoryview, start: int, count: int) -> memoryview | list[int]:
+    # Zero-copy view of a little-endian uint32 array, copied only on big-endian hosts
+    if sys.byteorder == "little" and start % 4 == 0:
+        return data[start:start + 4 * count].cast("I")
+    return list(struct.unpack_from(f"<{count}I", data, start))
+
+
+def pack_artifact(state: dict) -> bytes:
+    """
+    Serialize a tokenizer state into the binary layout read by unpack_artifact
+
+    The vocabulary and merges, which dominate load time, are stored as flat little-endian
+    uint32 arrays and one UTF-8 block of token texts, so reading them needs no parsing.
+    The remaining (small) fields are stored as a JSON metadata block.
+
+    Layout, every section 4-byte aligned :
+        header, token ids [n], token text offsets [n + 1], merges [m x (left, right, new_id)],
+        token texts, metadata JSON
+
+    Args:
+        state (dict) : The tokenizer state, as saved by BPETokenizer.save
+
+    Returns:
+        bytes : The serialized artifact
+    """
+
+    token_ids = sorted(state["vocab"])
+    texts = [state["vocab"][token_id].encode("utf-8", "surrogatepass") for token_id in token_ids]
+    offsets = [0]
+    for text in texts:
+        offsets.append(offsets[-1] + len(text))
+    merges = [value for merge in state["merges"] for value in (*merge["pair"], merge["new_id"])]
+    metadata = {key : value for key, value in state.items() if key not in ("vocab", "merges")}
+    metadata = json.dumps(metadata, ensure_ascii=False).encode("utf-8")
+
+    text_block = b"".join(texts)
+    text_block += b"\0" * (-len(text_block) % 4)
+    header = HEADER.pack(EMBEDDED_MAGIC, EMBEDDED_VERSION, len(token_ids), len(state["merges"]), len(metadata), offsets[-1])
+    return header + _uint32_array(token_ids) + _uint32_array(offsets) + _uint32_array(merges) + text_block + metadata
+
+
+def unpack_artifact(data: bytes | memoryview) -> tuple[list[int], list[str], list[int], dict]:
+    """
+    Read an artifact written by pack_artifact
+
+    Args:
+        data (bytes or memoryview) : The serialized artifact
+
+    Returns:
+        tuple : Token ids, token texts, flat merges (left, right, new_id, ...) and the metadata dict
+    """
+
+    data = memoryview(data).cast("B")
+    if len(data) < HEADER.size or bytes(data[:4]) != EMBEDDED_MAGIC:
+        raise ValueError("Not an embedded Lucid artifact (bad magic)")
+    _, version, num_tokens, num_merges, metadata_length, text_length = HEADER.unpack_from(data)
+    if version != EMBEDDED_VERSION:
+        raise ValueError(f"Unsupported embedded artifact version {version} (this Lucid reads version {EMBEDDED_VERSION})")
+
+    # Check the section sizes from the header before viewing any array
+    expected = HEADER.size + 4 * (2 * num_tokens + 1) + 12 * num_merges + text_length + (-text_length % 4) + metadata_length
+    if expected != len(data):
+        raise ValueError("Truncated or corrupted embedded Lucid artifact")
+
+    position = HEADER.size
+    token_ids = _uint32_view(data, position, num_tokens)
+    position += 4 * num_tokens
+    offsets = _uint32_view(data, position, num_tokens + 1)
+    position += 4 * (num_tokens + 1)
+    merges = _uint32_view(data, position, 3 * num_merges)
+    position += 12 * num_merges
+
+    text_block = bytes(data[position:position + text_length])
+    position += text_length + (-text_length % 4)
+    texts = [text_block[offsets[i]:offsets[i + 1]].decode("utf-8", "surrogatepass") for i in range(num_tokens)]
+    metadata = json.loads(bytes(data[position:]).decode("utf-8"))
+    return token_ids, texts, merges, metadata
@@ -0,0 +1,87 @@
+from dataclasses import dataclass, field
+
+
+@dataclass
+class Encoding:
+    # Token IDs, including any padding
+    ids: list[int]
+    # 1 for real tokens, 0 for padding
+    attention_mask: list[int] | None = None
+    # Segment index of each token (0 for the first segment, 1 for the second)
+    token_type_ids: list[int] | None = None
+    # Index of the input text this encoding was produced from
+    source_index: int | None = None
+    # (start, end) character span of each token in the source text
+    offsets: list[tuple[int, int]] | None = None
+    # Index of the pre-tokenized word each token came from, None for special tokens and newlines
+    word_ids: list[int | None] | None = None
+
+
+    def __len__(self) -> int:
+        return len(self.ids)
+
+
+    def char_to_token(self, char_index: int) -> int | None:
+        """
+        Find the token covering a character of the source text
+
+        Args:
+            char_index (int) : Character position in the source text
+
+        Returns:
+            int or None : Index of the first token whose span contains the character
+        """
+
+        if self.offsets is None:
+            raise ValueError("This encoding has no offsets")
+        for token_index, (start, end) in enumerate(self.offsets):
+            if start <= char_index < end:
+                return token_index
+        return None
+
+
+    def token_to_chars(self, token_index: int) -> tuple[int, int] | None:
+        """
+        Get the character span of a token in the source text
+
+        Args:
+            token_index (int) : Index of the token in the encoding
+
+        Returns:
+            tuple[int, int] or None : (start, end) span, None for tokens not backed by text
+        """
+
+        if self.offsets is None:
+            raise ValueError("This encoding has no offsets")
+        start, end = self.offsets[token_index]
+        return None if start == end else (start, end)
+
+
+@dataclass
+class Segment:
+    # Text of the segment, encoded on its own
+    text: str = ""
+    # Control tokens placed before the text, as special token strings or ids
+    prefix_tokens: list[str | int] = field(default_factory=list)
+    # Control tokens placed after the text, as special token strings or ids
+    suffix_tokens: list[str | int] = field(default_factory=list)
+
+
+@dataclass
+class PackedBatch:
+    # Fixed-length rows of token ids, each holding one or more eos-separated sequences followed by padding
+    ids: list[list[int]] = field(default_factory=list)
+    # 1 for real tokens, 0 for padding
+    attention_mask: list[list[int]] = field(default_factory=list)
+    # Index of the segment (within its row) each token belongs to, -1 for padding
+    segment_ids: list[list[int]] = field(default_factory=list)
+    # Position of each token within its segment, restarting at 0 for every segment (0 for padding)
+    position_ids: list[list[int]] = field(default_factory=list)
+    # (start, end) token span of every segment of each row
+    boundaries: list[list[tuple[int, int]]] = field(default_factory=list)
+    # Index of the input sequence each segment of each row comes from
+    source_indices: list[list[int]] = field(default_factory=list)
+
+
+    def __len__(self) -> int:
+        return len(self.ids)
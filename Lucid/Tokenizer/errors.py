@@ -0,0 +1,190 @@
+class LucidError(ValueError):
+    # Stable machine-readable identifier of the error kind
+    code = "lucid_error"
+
+    def __init__(self, message: str, offset: int | None = None, byte_offset: int | None = None):
+        """
+        Base class of the errors raised by the tokenizer
+
+        Subclasses ValueError so existing `except ValueError` handlers keep working.
+
+        Args:
+            message (str) : Description of the error
+            offset (int or None) : Character offset in the input text where the error occurred
+            byte_offset (int or None) : UTF-8 byte offset in the input text where the error occurred
+        """
+
+        self.message = message
+        self.offset = offset
+        self.byte_offset = byte_offset
+        super().__init__(message)
+
+
+    def __str__(self) -> str:
+        location = ""
+        if self.offset is not None:
+            location = f" (at character {self.offset}"
+            location += f", byte {self.byte_offset})" if self.byte_offset is not None else ")"
+        return f"[{self.code}] {self.message}{location}"
+
+
+    def locate(self, text: str, offset: int) -> "LucidError":
+        """
+        Record where in the input text the error occurred
+
+        Args:
+            text (str) : The input text
+            offset (int) : Character offset of the error in the text
+
+        Returns:
+            LucidError : The error itself, for `raise error.locate(...)`
+        """
+
+        self.offset = offset
+        self.byte_offset = len(text[:offset].encode("utf-8", "surrogatepass"))
+        return self
+
+
+class LucidNotTrained(LucidError):
+    code = "not_trained"
+
+    def __init__(self, operation: str):
+        self.operation = operation
+        super().__init__(f"Cannot {operation} with an untrained tokenizer. Train it or load a saved one first.")
+
+
+class LucidFrozen(LucidError):
+    code = "frozen"
+
+    def __init__(self, operation: str):
+        self.operation = operation
+        super().__init__(f"Cannot {operation} a frozen tokenizer : only special tokens and merges can be added after freeze().")
+
+
+class LucidCharacterNotFound(LucidError):
+    code = "character_not_found"
+
+    def __init__(self, chars: list[str], offset: int | None = None):
+        self.chars = chars
+        super().__init__(f"Characters not found in vocab : {chars}", offset)
+
+
+class LucidTokenIdNotFound(LucidError):
+    code = "token_id_not_found"
+
+    def __init__(self, token_id: int, index: int | None = None):
+        self.token_id = token_id
+        # Position of the unknown id in the decoded sequence
+        self.index = index
+        position = f" at position {index}" if index is not None else ""
+        super().__init__(f"Token ID {token_id} not found in vocab{position}")
+
+
+class LucidSpecialTokenNotFound(LucidError):
+    code = "special_token_not_found"
+
+    def __init__(self, token: str):
+        self.token = token
+        super().__init__(f"Special token {token} not found in vocabulary.")
+
+
+class LucidDisallowedSpecialToken(LucidError):
+    code = "disallowed_special_token"
+
+    def __init__(self, tokens: list[str]):
+        self.tokens = tokens
+        super().__init__(f"Disallowed special tokens encountered in text : {tokens}")
+
+
+class LucidSpecialTokenNotSet(LucidError):
+    code = "special_token_not_set"
+
+    def __init__(self, role: str):
+        self.role = role
+        super().__init__(f"No {role} token is set. Assign one with set_special_token('{role}', token).")
+
+
+class LucidLanguageTagNotSet(LucidError):
+    code = "language_tag_not_set"
+
+    def __init__(self, language: str):
+        self.language = language
+        super().__init__(f"No language tag is registered for {language!r}. Register one with register_language_tags(['{language}']).")
+
+
+class LucidTokenGroupNotSet(LucidError):
+    code = "token_group_not_set"
+
+    def __init__(self, name: str):
+        self.name = name
+        super().__init__(f"No token group is registered as {name!r}. Register one with register_token_group('{name}', tokens).")
+
+
+class LucidPostProcessorNotSet(LucidError):
+    code = "post_processor_not_set"
+
+    def __init__(self):
+        super().__init__("No post-processor is set. Assign one with set_post_processor.")
+
+
+class LucidInvalidMerge(LucidError):
+    code = "invalid_merge"
+
+    def __init__(self, left: str, right: str, part: str):
+        self.left = left
+        self.right = right
+        super().__init__(f"Merge ({left!r}, {right!r}) references token {part!r} not found in vocabulary.")
+
+
+class LucidInvalidConfig(LucidError):
+    code = "invalid_config"
+
+    def __init__(self, message: str):
+        super().__init__(message)
+
+
+class LucidFingerprintMismatch(LucidError):
+    code = "fingerprint_mismatch"
+
+    def __init__(self, expected: str, actual: str):
+        self.expected = expected
+        self.actual = actual
+        super().__init__(f"Tokenizer fingerprint {actual} does not match the expected {expected}.")
+
+
+class LucidUnsupportedVersion(LucidError):
+    code = "unsupported_version"
+
+    def __init__(self, version: object, supported: int):
+        self.version = version
+        self.supported = supported
+        super().__init__(f"Artifact config version {version!r} is not supported (this Lucid reads up to version {supported}). "
+                         "Upgrade Lucid to load it.")
+
+
+class LucidEncodeLimitExceeded(LucidError):
+    code = "encode_limit_exceeded"
+
+    def __init__(self, limit: str, maximum: int, actual: int):
+        # Name of the exceeded EncodeLimits field
+        self.limit = limit
+        self.maximum = maximum
+        # Size reached when the limit was detected (a lower bound for max_output_tokens)
+        self.actual = actual
+        super().__init__(f"Encode limit {limit}={maximum} exceeded (reached {actual}).")
+
+
+class LucidBlankInput(LucidError):
+    code = "blank_input"
+
+    def __init__(self):
+        super().__init__("Cannot encode an empty or whitespace-only text (TokenizerConfig.blank_input is BlankInputPolicy.ERROR).")
+
+
+class LucidMissingDirectory(LucidError):
+    code = "missing_directory"
+
+    def __init__(self, path: str, directory: str):
+        self.path = path
+        self.directory = directory
+        super().__init__(f"Cannot write {path!r} : directory {directory!r} does not exist. Create it or pass create_dirs=True.")
@@ -0,0 +1,74 @@
+import importlib.util
+
+# Optional dependencies required by each feature
+FEATURES = {
+    "arrow": ("pyarrow",),
+    "numpy": ("numpy",),
+    "hf-hub": ("huggingface_hub",),
+    "zstd": ("zstandard",),
+}
+
+
+class FeatureNotEnabled(ImportError):
+
+    def __init__(self, feature: str, missing: list[str]):
+        self.feature = feature
+        self.missing = missing
+        super().__init__(
+            f"Feature '{feature}' is not enabled : missing optional dependencies {missing}. "
+            f"Install them with `pip install Lucid[{feature}]`"
+        )
+
+
+def missing_dependencies(feature: str) -> list[str]:
+    """
+    List the optional dependencies of a feature that are not installed
+
+    Args:
+        feature (str) : The feature name
+
+    Returns:
+        list[str] : The missing module names
+    """
+
+    if feature not in FEATURES:
+        raise ValueError(f"Unknown feature : {feature}. Available features : {sorted(FEATURES)}")
+    return [module for module in FEATURES[feature] if importlib.util.find_spec(module) is None]
+
+
+def is_feature_enabled(feature: str) -> bool:
+    """
+    Check whether all optional dependencies of a feature are installed
+
+    Args:
+        feature (str) : The feature name
+
+    Returns:
+        bool : True if the feature can be used
+    """
+
+    return not missing_dependencies(feature)
+
+
+def enabled_features() -> dict[str, bool]:
+    """
+    Report the availability of every optional feature
+
+    Returns:
+        dict[str, bool] : Mapping of feature name to availability
+    """
+
+    return {feature : is_feature_enabled(feature) for feature in FEATURES}
+
+
+def require_feature(feature: str) -> None:
+    """
+    Raise FeatureNotEnabled if a feature's optional dependencies are absent
+
+    Args:
+        feature (str) : The feature name
+    """
+
+    missing = missing_dependencies(feature)
+    if missing:
+        raise FeatureNotEnabled(feature, missing)
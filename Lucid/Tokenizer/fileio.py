@@ -0,0 +1,191 @@
+from contextlib import contextmanager, nullcontext
+from typing import IO
+import gzip
+import io
+import lzma
+import os
+import tempfile
+
+from .errors import LucidMissingDirectory
+from .features import require_feature
+
+try:
+    import fcntl
+except ImportError:
+    # Windows
+    fcntl = None
+    import msvcrt
+
+# A filesystem path, bytes for names that are not valid UTF-8
+FilePath = str | bytes | os.PathLike
+# A filesystem path or an already opened text file-like object
+PathOrFile = FilePath | IO[str]
+
+# Suffix of the advisory lock file created next to a locked path
+LOCK_SUFFIX = ".lock"
+
+# File extensions decompressed on the fly when reading
+COMPRESSED_SUFFIXES = (".gz", ".xz", ".zst")
+
+
+def fs_path(target: FilePath) -> str:
+    """
+    Convert a path to the str form used by the file functions
+
+    Bytes and bytes-based PathLike objects are decoded with the filesystem encoding and its error
+    handler (surrogateescape on POSIX), so names that are not valid UTF-8 still map to the same file.
+
+    Args:
+        target (str, bytes or PathLike) : The path
+
+    Returns:
+        str : The path as a str
+    """
+
+    return os.fsdecode(os.fspath(target))
+
+
+def prepare_write_path(target: FilePath, create_dirs: bool = False) -> str:
+    """
+    Check that the directory of a path about to be written exists, creating it if asked
+
+    Args:
+        target (str, bytes or PathLike) : The path to be written
+        create_dirs (bool) : Create the missing parent directories instead of raising LucidMissingDirectory
+
+    Returns:
+        str : The path as a str
+    """
+
+    path = fs_path(target)
+    directory = os.path.dirname(os.path.abspath(path))
+    if not os.path.isdir(directory):
+        if not create_dirs:
+            raise LucidMissingDirectory(path, directory)
+        os.makedirs(directory, exist_ok=True)
+    return path
+
+
+def open_compressed(path: FilePath) -> IO[str]:
+    """
+    Open a gzip, xz or zstandard compressed UTF-8 file for streamed text reading
+
+    The compression is told by the extension (.gz, .xz, .zst); zstandard needs the "zstd" feature.
+
+    Args:
+        path (str, bytes or PathLike) : The compressed file
+
+    Returns:
+        IO[str] : The decompressed text stream
+    """
+
+    path = fs_path(path)
+    if path.endswith(".gz"):
+        return gzip.open(path, "rt", encoding="utf-8")
+    if path.endswith(".xz"):
+        return lzma.open(path, "rt", encoding="utf-8")
+    if path.endswith(".zst"):
+        require_feature("zstd")
+        import zstandard
+
+        raw = open(path, "rb")
+        try:
+            return io.TextIOWrapper(zstandard.ZstdDecompressor().stream_reader(raw, closefd=True), encoding="utf-8")
+        except BaseException:
+            raw.close()
+            raise
+    raise ValueError(f"Unknown compression of {path!r}, expected one of {COMPRESSED_SUFFIXES}")
+
+
+@contextmanager
+def open_text(target: PathOrFile, mode: str, atomic: bool = False, lock: bool = False, create_dirs: bool = False):
+    """
+    Open a path for text I/O, or pass an already opened file-like object through
+
+    File-like objects are left open so the caller keeps ownership of them.
+
+    Args:
+        target (str, bytes, PathLike or file-like) : The path or file object
+        mode (str) : "r" or "w"
+        atomic (bool) : When writing a path, write a temporary file in the same directory and rename
+            it over the target once complete, so readers never see a partially written file
+        lock (bool) : Hold an advisory lock on the path (see file_lock) while it is open
+        create_dirs (bool) : When writing a path, create its missing parent directories
+
+    Paths ending in .gz, .xz or .zst are decompressed on the fly when read (see open_compressed).
+    """
+
+    if hasattr(target, "read") or hasattr(target, "write"):
+        yield target
+        return
+
+    path = prepare_write_path(target, create_dirs) if mode == "w" else fs_path(target)
+    with file_lock(path) if lock else nullcontext():
+        if mode == "r" and path.endswith(COMPRESSED_SUFFIXES):
+            with open_compressed(path) as file:
+                yield file
+            return
+        if not (atomic and mode == "w"):
+            with open(path, mode, encoding="utf-8") as file:
+                yield file
+            return
+
+        fd, temp_path = tempfile.mkstemp(dir=os.path.dirname(os.path.abspath(path)), prefix=os.path.basename(path) + ".", suffix=".tmp")
+        try:
+            with os.fdopen(fd, "w", encoding="utf-8") as file:
+                # mkstemp creates owner-only files : keep the permissions of the file being replaced
+                os.chmod(temp_path, os.stat(path).st_mode & 0o777 if os.path.exists(path) else 0o644)
+                yield file
+                file.flush()
+                os.fsync(file.fileno())
+            os.replace(temp_path, path)
+        except BaseException:
+            os.unlink(temp_path)
+            raise
+
+
+@contextmanager
+def open_binary(target: FilePath | IO[bytes], mode: str, create_dirs: bool = False):
+    """
+    Open a path for binary I/O, or pass an already opened binary file-like object through
+
+    Args:
+        target (str, bytes, PathLike or binary file-like) : The path or file object
+        mode (str) : "rb" or "wb"
+        create_dirs (bool) : When writing a path, create its missing parent directories
+    """
+
+    if hasattr(target, "read") or hasattr(target, "write"):
+        yield target
+        return
+
+    path = prepare_write_path(target, create_dirs) if mode == "wb" else fs_path(target)
+    with open(path, mode) as file:
+        yield file
+
+
+@contextmanager
+def file_lock(path: FilePath):
+    """
+    Hold an exclusive advisory lock associated with a path
+
+    The lock is taken on a separate "<path>.lock" file, so it survives the path itself
+    being replaced by an atomic write. Only processes that also lock cooperate.
+
+    Args:
+        path (str, bytes or PathLike) : The path to lock
+    """
+
+    with open(fs_path(path) + LOCK_SUFFIX, "a") as lock_file:
+        if fcntl is not None:
+            fcntl.flock(lock_file.fileno(), fcntl.LOCK_EX)
+        else:
+            msvcrt.locking(lock_file.fileno(), msvcrt.LK_LOCK, 1)
+        try:
+            yield
+        finally:
+            if fcntl is not None:
+                fcntl.flock(lock_file.fileno(), fcntl.LOCK_UN)
+            else:
+                lock_file.seek(0)
+                msvcrt.locking(lock_file.fileno(), msvcrt.LK_UNLCK, 1)
@@ -0,0 +1,76 @@
+from typing import IO
+import struct
+
+# File magic and format version written by write_gguf_vocab
+GGUF_MAGIC = b"GGUF"
+GGUF_VERSION = 3
+
+# Alignment of the (empty) tensor data section
+GGUF_ALIGNMENT = 32
+
+# GGUF metadata value types used here
+GGUF_TYPE_UINT32 = 4
+GGUF_TYPE_INT32 = 5
+GGUF_TYPE_STRING = 8
+GGUF_TYPE_ARRAY = 9
+
+# Token types of tokenizer.ggml.token_type, as defined by llama.cpp
+TOKEN_TYPE_NORMAL = 1
+TOKEN_TYPE_UNKNOWN = 2
+TOKEN_TYPE_CONTROL = 3
+TOKEN_TYPE_UNUSED = 5
+
+
+def _string(value: str | bytes) -> bytes:
+    data = value.encode("utf-8", "surrogatepass") if isinstance(value, str) else value
+    return struct.pack("<Q", len(data)) + data
+
+
+def _value(value_type: int, value) -> bytes:
+    if value_type == GGUF_TYPE_STRING:
+        return _string(value)
+    if value_type == GGUF_TYPE_UINT32:
+        return struct.pack("<I", value)
+    if value_type == GGUF_TYPE_INT32:
+        return struct.pack("<i", value)
+    raise ValueError(f"Unsupported GGUF value type {value_type}")
+
+
+def write_gguf_vocab(file: IO[bytes], tokens: list[str], token_types: list[int], merges: list[str],
+                     special_ids: dict[str, int], model: str = "gpt2") -> None:
+    """
+    Write a GGUF file holding only the tokenizer metadata section (no tensors)
+
+    The keys follow llama.cpp : tokenizer.ggml.model, tokenizer.ggml.tokens,
+    tokenizer.ggml.token_type, tokenizer.ggml.merges and tokenizer.ggml.<role>_token_id.
+
+    Args:
+        file (binary file-like) : Destination of the GGUF data
+        tokens (list[str]) : Token strings, the index being the token id
+        token_types (list[int]) : llama.cpp token type of every token
+        merges (list[str]) : Merges in rank order, as "left right"
+        special_ids (dict[str, int]) : Id of each special token role ("bos", "eos", "pad", "unk")
+        model (str) : Value of tokenizer.ggml.model
+    """
+
+    metadata = [
+        ("tokenizer.ggml.model", GGUF_TYPE_STRING, model),
+        ("tokenizer.ggml.tokens", GGUF_TYPE_STRING, tokens),
+        ("tokenizer.ggml.token_type", GGUF_TYPE_INT32, token_types),
+        ("tokenizer.ggml.merges", GGUF_TYPE_STRING, merges),
+    ]
+    metadata.extend((f"tokenizer.ggml.{role}_token_id", GGUF_TYPE_UINT32, token_id) for role, token_id in special_ids.items())
+
+    data = bytearray(GGUF_MAGIC + struct.pack("<IQQ", GGUF_VERSION, 0, len(metadata)))
+    for key, value_type, value in metadata:
+        data += _string(key)
+        if isinstance(value, list):
+            data += struct.pack("<IIQ", GGUF_TYPE_ARRAY, value_type, len(value))
+            for item in value:
+                data += _value(value_type, item)
+        else:
+            data += struct.pack("<I", value_type) + _value(value_type, value)
+
+    # The tensor data section starts aligned, even though it is empty
+    data += b"\0" * (-len(data) % GGUF_ALIGNMENT)
+    file.write(data)
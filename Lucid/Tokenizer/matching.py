@@ -0,0 +1,100 @@
+from collections import deque
+
+
+class AhoCorasick:
+
+    def __init__(self, patterns: list[str]):
+        # Patterns indexed by their position in the input list
+        self.patterns = list(patterns)
+        # Trie transitions : state -> {char: next_state}
+        self.goto = [{}]
+        # Failure links : state -> longest proper suffix state
+        self.fail = [0]
+        # Pattern indices that end at each state (including via failure links)
+        self.output = [[]]
+        # Length of the string spelled by each state
+        self.depth = [0]
+
+        for index, pattern in enumerate(self.patterns):
+            if not pattern:
+                raise ValueError("Patterns must be non-empty strings")
+            state = 0
+            for char in pattern:
+                if char not in self.goto[state]:
+                    self.goto.append({})
+                    self.fail.append(0)
+                    self.output.append([])
+                    self.depth.append(self.depth[state] + 1)
+                    self.goto[state][char] = len(self.goto) - 1
+                state = self.goto[state][char]
+            self.output[state].append(index)
+
+        # Breadth-first construction of the failure links
+        queue = deque(self.goto[0].values())
+        while queue:
+            state = queue.popleft()
+            for char, next_state in self.goto[state].items():
+                queue.append(next_state)
+                fallback = self.fail[state]
+                while fallback and char not in self.goto[fallback]:
+                    fallback = self.fail[fallback]
+                self.fail[next_state] = self.goto[fallback].get(char, 0)
+                self.output[next_state] = self.output[next_state] + self.output[self.fail[next_state]]
+
+
+    def step(self, state: int, char: str) -> int:
+        """
+        Advance the automaton by a single character
+
+        Args:
+            state (int) : The current state
+            char (str) : The next character of the input
+
+        Returns:
+            int : The state after consuming the character
+        """
+
+        while state and char not in self.goto[state]:
+            state = self.fail[state]
+        return self.goto[state].get(char, 0)
+
+
+    def find_all(self, text: str):
+        """
+        Find all (possibly overlapping) pattern occurrences in the text
+
+        Args:
+            text (str) : The text to search
+
+        Yields:
+            tuple[int, int, int] : (start, end, pattern_index) for every match
+        """
+
+        state = 0
+        for i, char in enumerate(text):
+            state = self.step(state, char)
+            for index in self.output[state]:
+                yield i + 1 - len(self.patterns[index]), i + 1, index
+
+
+    def find_leftmost_longest(self, text: str) -> list[tuple[int, int, int]]:
+        """
+        Find non-overlapping pattern occurrences, preferring the leftmost then the longest match
+
+        When one pattern is a prefix of another (e.g. "<|im|>" and "<|im_start|>"), the longer one
+        wins wherever both match at the same position.
+
+        Args:
+            text (str) : The text to search
+
+        Returns:
+            list[tuple[int, int, int]] : (start, end, pattern_index) for every selected match, in text order
+        """
+
+        matches = []
+        last_end = 0
+        for start, end, index in sorted(self.find_all(text), key=lambda match: (match[0], -match[1])):
+            if start >= last_end:
+                matches.append((start, end, index))
+                last_end = end
+        return matches
@@ -0,0 +1,36 @@
+import sys
+
+
+def deep_sizeof(obj: object, seen: set[int] | None = None) -> int:
+    """
+    Approximate the memory held by an object and everything it references
+
+    Objects already in seen are not counted again, so sharing one seen set across
+    calls attributes each shared object (e.g. a token string referenced by both
+    vocab and inverse_vocab) to the first structure that reaches it.
+
+    Args:
+        obj (object) : The object to measure
+        seen (set[int] or None) : Ids of objects already counted, updated in place
+
+    Returns:
+        int : The size in bytes
+    """
+
+    seen = set() if seen is None else seen
+    size = 0
+    stack = [obj]
+    while stack:
+        item = stack.pop()
+        if id(item) in seen:
+            continue
+        seen.add(id(item))
+        size += sys.getsizeof(item)
+        if isinstance(item, dict):
+            stack.extend(item.keys())
+            stack.extend(item.values())
+        elif isinstance(item, (list, tuple, set, frozenset)):
+            stack.extend(item)
+        elif hasattr(item, "__dict__") and not isinstance(item, type):
+            stack.append(vars(item))
+    return size
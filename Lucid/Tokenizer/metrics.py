@@ -0,0 +1,89 @@
+import threading
+
+from .reports import MetricsSnapshot
+
+# Counter names passed to Metrics.increment
+TEXTS_ENCODED = "texts_encoded"
+TOKENS_PRODUCED = "tokens_produced"
+ENCODE_ERRORS = "encode_errors"
+CACHE_HITS = "cache_hits"
+CACHE_MISSES = "cache_misses"
+
+
+class Metrics:
+    """
+    Receiver of the counters reported by a tokenizer (see BPETokenizer.set_metrics)
+
+    Subclass it and override increment to forward the counters to a monitoring system,
+    e.g. prometheus_client counters keyed by name.
+    """
+
+    def increment(self, name: str, value: int = 1, labels: dict[str, str] | None = None) -> None:
+        """
+        Add to a counter
+
+        Args:
+            name (str) : The counter : "texts_encoded", "tokens_produced", "encode_errors" (labeled with the
+                error code as "kind"), "cache_hits" or "cache_misses"
+            value (int) : The amount added
+            labels (dict[str, str] or None) : Labels distinguishing series of the counter
+        """
+
+        raise NotImplementedError
+
+
+class InMemoryMetrics(Metrics):
+
+    def __init__(self):
+        """
+        Thread-safe in-memory counters, read with snapshot()
+
+        Counters of encodes run in worker processes (parallel batch and corpus encoding) stay in
+        those processes and are not included.
+        """
+
+        self._lock = threading.Lock()
+        self._counters = {}
+
+
+    def increment(self, name: str, value: int = 1, labels: dict[str, str] | None = None) -> None:
+        key = (name, tuple(sorted(labels.items())) if labels else ())
+        with self._lock:
+            self._counters[key] = self._counters.get(key, 0) + value
+
+
+    def snapshot(self) -> MetricsSnapshot:
+        """
+        Read the current counters
+
+        Returns:
+            MetricsSnapshot : The counter values
+        """
+
+        with self._lock:
+            counters = dict(self._counters)
+        return MetricsSnapshot(
+            texts_encoded=counters.get((TEXTS_ENCODED, ()), 0),
+            tokens_produced=counters.get((TOKENS_PRODUCED, ()), 0),
+            errors={dict(labels)["kind"] : value for (name, labels), value in counters.items() if name == ENCODE_ERRORS},
+            cache_hits=counters.get((CACHE_HITS, ()), 0),
+            cache_misses=counters.get((CACHE_MISSES, ()), 0),
+        )
+
+
+    def reset(self) -> None:
+        """
+        Set every counter back to zero
+        """
+
+        with self._lock:
+            self._counters = {}
+
+
+    def __getstate__(self) -> dict:
+        # Locks cannot be pickled : worker processes get fresh counters
+        return {}
+
+
+    def __setstate__(self, state: dict) -> None:
+        self.__init__()
@@ -0,0 +1,98 @@
+import codecs
+import json
+
+from .config import PreTokenizer, TokenizerConfig, WordBoundary
+from .errors import LucidInvalidMerge
+from .fileio import PathOrFile, open_text
+from .tokenizer import CAP_TOKEN, END_OF_WORD, UPPER_TOKEN, BPETokenizer
+
+# Space markers recognized when inferring the config of an old artifact
+KNOWN_SPACE_MARKERS = ("Ġ", "▁")
+
+
+def infer_config(vocab: dict[int, str], merges: dict[tuple[int, int], int]) -> TokenizerConfig:
+    """
+    Guess the settings an artifact without a saved config was trained with, from its tokens
+
+    Args:
+        vocab (dict[int, str]) : Mapping of token id to token
+        merges (dict[tuple[int, int], int]) : Mapping of merged pair to the id of the merged token
+
+    Returns:
+        TokenizerConfig : The inferred settings (split_digits is never inferred)
+    """
+
+    merged = [vocab[new_id] for new_id in merges.values() if new_id in vocab]
+    case_markers = CAP_TOKEN in vocab.values() or UPPER_TOKEN in vocab.values()
+
+    if any(token.endswith(END_OF_WORD) for token in merged):
+        space_marker, word_boundary = None, WordBoundary.END
+    else:
+        counts = {marker : sum(token.startswith(marker) for token in merged) for marker in KNOWN_SPACE_MARKERS}
+        space_marker, word_boundary = max(counts, key=counts.get), WordBoundary.START
+        if counts[space_marker] == 0:
+            space_marker = "Ġ"
+
+    # Only the code pre-tokenizer produces tokens with a marker after their first character (e.g. indentation)
+    code = space_marker is not None and any(space_marker in token[1:] for token in merged)
+
+    # Byte-level tokens only use the 256 byte characters, and their non-ASCII ones spell UTF-8 sequences
+    non_ascii = [token.replace(space_marker or "", "") for token in merged if any(ord(char) > 127 for char in token)]
+    non_ascii = [token for token in non_ascii if token]
+    byte_level = not case_markers and all(ord(char) < 256 for token in non_ascii for char in token)
+    if byte_level:
+        fragments = [fragment for fragment in map(_is_utf8_fragment, non_ascii) if fragment is not None]
+        byte_level = bool(fragments) and sum(fragments) * 2 >= len(fragments)
+
+    return TokenizerConfig(
+        case_markers=case_markers,
+        space_marker=space_marker,
+        pre_tokenizer=PreTokenizer.CODE if code else PreTokenizer.WHITESPACE,
+        byte_level=byte_level,
+        word_boundary=word_boundary,
+    )
+
+
+def _is_utf8_fragment(token: str) -> bool | None:
+    # Whether the characters, read as bytes, are valid UTF-8 up to sequences cut at either end. None when only cut
+    # sequences are non-ASCII, which plain text does too (e.g. "café" read as bytes ends with a cut sequence)
+    data = token.encode("latin-1").lstrip(bytes(range(0x80, 0xC0)))
+    try:
+        text = codecs.getincrementaldecoder("utf-8")().decode(data, final=False)
+    except UnicodeDecodeError:
+        return False
+    return True if any(ord(char) > 127 for char in text) else None
+
+
+def migrate_artifact(old_vocab: PathOrFile, old_merges: PathOrFile, out_path: PathOrFile,
+                     config: TokenizerConfig | None = None) -> BPETokenizer:
+    """
+    Convert a two-file artifact (save_vocab_and_merges) of an earlier version into the single-file format of save
+
+    The config, which two-file artifacts do not record, is inferred from the tokens unless given.
+    Every merge is checked against the vocabulary before anything is written.
+
+    Args:
+        old_vocab (str, PathLike or file-like) : The vocabulary JSON file ({id : token})
+        old_merges (str, PathLike or file-like) : The merges JSON file ([{"pair" : [a, b], "new_id" : id}, ...])
+        out_path (str, PathLike or file-like) : Where to write the migrated tokenizer
+        config (TokenizerConfig or None) : Settings of the tokenizer, inferred when None
+
+    Returns:
+        BPETokenizer : The migrated tokenizer
+    """
+
+    with open_text(old_vocab, "r") as file:
+        vocab = {int(token_id) : token for token_id, token in json.load(file).items()}
+    with open_text(old_merges, "r") as file:
+        merges = {tuple(merge["pair"]) : merge["new_id"] for merge in json.load(file)}
+
+    for (left, right), new_id in merges.items():
+        for token_id in (left, right, new_id):
+            if token_id not in vocab:
+                raise LucidInvalidMerge(vocab.get(left, str(left)), vocab.get(right, str(right)), str(token_id))
+
+    tokenizer = BPETokenizer(config or infer_config(vocab, merges))
+    tokenizer._load_state({"vocab" : vocab, "merges" : [{"pair" : list(pair), "new_id" : new_id} for pair, new_id in merges.items()]})
+    tokenizer.save(out_path)
+    return tokenizer
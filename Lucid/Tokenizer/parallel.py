@@ -0,0 +1,119 @@
+from collections import deque
+from concurrent.futures import ProcessPoolExecutor
+from typing import Callable, Iterable, Iterator
+import os
+
+# Environment variables read (in order) when no explicit thread count was set
+NUM_THREADS_ENV_VARS = ("LUCID_NUM_THREADS", "RAYON_NUM_THREADS")
+
+# Thread count set with set_num_threads, None to use the environment or a single worker
+_num_threads = None
+
+
+def set_num_threads(num_threads: int | None) -> None:
+    """
+    Set the number of workers used by every parallel path (training, batch encode and decode, encode_corpus)
+
+    Parallelism is opt-in : every path runs in the calling process unless this, LUCID_NUM_THREADS or
+    RAYON_NUM_THREADS asks for more than one worker. More than one starts a process pool, so on spawn
+    platforms (Windows, macOS) the script must then run under if __name__ == "__main__".
+
+    Args:
+        num_threads (int or None) : The number of workers, None to restore the default
+    """
+
+    global _num_threads
+    if num_threads is not None and num_threads < 1:
+        raise ValueError("num_threads must be at least 1")
+    _num_threads = num_threads
+
+
+def get_num_threads() -> int:
+    """
+    Number of workers used by parallel paths
+
+    Returns:
+        int : The value of set_num_threads, else LUCID_NUM_THREADS or RAYON_NUM_THREADS, else 1
+    """
+
+    if _num_threads is not None:
+        return _num_threads
+    for name in NUM_THREADS_ENV_VARS:
+        value = os.environ.get(name, "").strip()
+        if value:
+            if not value.isdigit() or int(value) < 1:
+                raise ValueError(f"{name} must be a positive integer, got {value!r}")
+            return int(value)
+    return 1
+
+
+def default_num_workers() -> int:
+    """
+    Number of worker processes used by parallel paths
+
+    Returns:
+        int : The configured number of threads
+    """
+
+    return get_num_threads()
+
+
+def parallel_map(func: Callable, items: Iterable, num_workers: int | None = None) -> list:
+    """
+    Map a picklable function over items using a process pool, preserving order
+
+    Falls back to a plain loop when a single worker would be used.
+
+    Args:
+        func (callable) : A module-level function to apply
+        items (iterable) : The inputs
+        num_workers (int or None) : Number of processes, defaults to the configured thread count
+
+    Returns:
+        list : The results in input order
+    """
+
+    items = list(items)
+    workers = min(num_workers or default_num_workers(), len(items))
+    if workers <= 1:
+        return [func(item) for item in items]
+
+    with ProcessPoolExecutor(max_workers=workers) as executor:
+        return list(executor.map(func, items))
+
+
+def imap_ordered(func: Callable, items: Iterable, num_workers: int | None = None, max_pending: int | None = None,
+                 initializer: Callable | None = None, initargs: tuple = ()) -> Iterator:
+    """
+    Lazily map a picklable function over a stream of items with a process pool, preserving order
+
+    At most max_pending items are submitted ahead of the result being consumed, so a slow
+    consumer (e.g. a disk writer) holds back reading instead of buffering the whole input.
+
+    Args:
+        func (callable) : A module-level function to apply
+        items (iterable) : The inputs, consumed lazily
+        num_workers (int or None) : Number of processes, defaults to the configured thread count
+        max_pending (int or None) : Maximum number of submitted but unconsumed items, defaults to twice the workers
+        initializer (callable or None) : Called once in every worker process, not run in-process without workers
+        initargs (tuple) : Arguments of the initializer
+
+    Yields:
+        The results in input order
+    """
+
+    workers = num_workers or default_num_workers()
+    if workers <= 1:
+        for item in items:
+            yield func(item)
+        return
+
+    max_pending = max_pending or 2 * workers
+    with ProcessPoolExecutor(max_workers=workers, initializer=initializer, initargs=initargs) as executor:
+        pending = deque()
+        for item in items:
+            pending.append(executor.submit(func, item))
+            if len(pending) >= max_pending:
+                yield pending.popleft().result()
+        while pending:
+            yield pending.popleft().result()
@@ -0,0 +1,93 @@
+from .errors import LucidSpecialTokenNotFound
+from .special_tokens import SPECIAL_TOKEN_ROLES
+
+# Placeholders standing for the encoded text segments
+FIRST_SEGMENT = ("{text}", "{a}")
+SECOND_SEGMENT = "{b}"
+
+
+class TemplateProcessor:
+
+    def __init__(self, single: str, pair: str | None = None):
+        """
+        Post-processor inserting special tokens around encoded segments
+
+        Templates are whitespace-separated items. "{text}" (or "{a}") and "{b}" stand
+        for the first and second segment, "{bos}", "{eos}", "{pad}" and "{unk}" for the
+        tokens assigned to those roles, and any other item is a literal vocabulary token
+        such as "[CLS]". An item may end with ":N" to set its token type id; by default
+        items before "{b}" get type 0 and the rest type 1.
+
+        Args:
+            single (str) : Template for a single segment, e.g. "{bos} {text} {eos}"
+            pair (str or None) : Template for two segments, e.g. "[CLS] {a} [SEP] {b} [SEP]"
+        """
+
+        self.single = single
+        self.pair = pair
+        self.single_items = self.parse(single)
+        self.pair_items = self.parse(pair) if pair is not None else None
+        if any(item == SECOND_SEGMENT for item, _ in self.single_items):
+            raise ValueError("The single template cannot reference the second segment {b}")
+
+
+    @staticmethod
+    def parse(template: str) -> list[tuple[str, int]]:
+        items = []
+        type_id = 0
+        for item in template.split():
+            name, _, suffix = item.rpartition(":")
+            if not name or not suffix.isdigit():
+                name, suffix = item, ""
+            if name == SECOND_SEGMENT:
+                type_id = 1
+            items.append((name, int(suffix) if suffix else type_id))
+        return items
+
+
+    def process(self, tokenizer, ids_a: list[int], ids_b: list[int] | None = None) -> tuple[list[int], list[int]]:
+        """
+        Apply the template to encoded segments
+
+        Args:
+            tokenizer (BPETokenizer) : The tokenizer providing special token ids
+            ids_a (list[int]) : Token IDs of the first segment
+            ids_b (list[int] or None) : Token IDs of the second segment
+
+        Returns:
+            tuple[list[int], list[int]] : The token IDs and their token type ids
+        """
+
+        if ids_b is None:
+            items = self.single_items
+        elif self.pair_items is None:
+            raise ValueError("This template has no pair template for two segments")
+        else:
+            items = self.pair_items
+
+        token_ids = []
+        type_ids = []
+        for name, type_id in items:
+            if name in FIRST_SEGMENT:
+                segment = ids_a
+            elif name == SECOND_SEGMENT:
+                segment = ids_b
+            elif name.startswith("{") and name.endswith("}") and name[1:-1] in SPECIAL_TOKEN_ROLES:
+                segment = [tokenizer.require_special_token_id(name[1:-1])]
+            elif name in tokenizer.inverse_vocab:
+                segment = [tokenizer.inverse_vocab[name]]
+            else:
+                raise LucidSpecialTokenNotFound(name)
+            token_ids.extend(segment)
+            type_ids.extend([type_id] * len(segment))
+
+        return token_ids, type_ids
+
+
+    def to_dict(self) -> dict[str, str | None]:
+        return {"single" : self.single, "pair" : self.pair}
+
+
+    @classmethod
+    def from_dict(cls, data: dict[str, str | None]) -> "TemplateProcessor":
+        return cls(data["single"], data.get("pair"))
@@ -0,0 +1,287 @@
+from dataclasses import dataclass, field
+
+
+@dataclass
+class CoverageReport:
+    # Number of pre-tokenized words in the texts
+    total_words: int = 0
+    # Number of tokens produced for those words
+    total_tokens: int = 0
+    # Characters missing from the vocabulary with their occurrence counts
+    missing_chars: dict[str, int] = field(default_factory=dict)
+    # Words containing missing characters (they fail to encode, or fall back to UNK) with their counts
+    unknown_words: dict[str, int] = field(default_factory=dict)
+    # Fraction of tokens that are a single character
+    single_char_token_ratio: float = 0.0
+    # Fraction of words that are not a single vocabulary token
+    oov_rate: float = 0.0
+
+
+@dataclass
+class CorpusEncodeStats:
+    # Number of input lines encoded
+    num_documents: int = 0
+    # Number of token ids written
+    num_tokens: int = 0
+
+
+@dataclass
+class EvalMetrics:
+    # Number of tokens produced for the held-out text
+    num_tokens: int = 0
+    # Number of pre-tokenized words in the held-out text
+    num_words: int = 0
+    # Tokens per UTF-8 byte of text (lower is better compression)
+    tokens_per_byte: float = 0.0
+    # Average number of tokens per word
+    fertility: float = 0.0
+    # Fraction of words encoded as a single token
+    whole_word_ratio: float = 0.0
+
+
+@dataclass
+class VocabStats:
+    # Number of regular (non-special) tokens
+    num_tokens: int = 0
+    # Number of special tokens of the form <|...|>
+    num_special_tokens: int = 0
+    # Token length in characters -> number of tokens of that length
+    length_histogram: dict[int, int] = field(default_factory=dict)
+    # Average token length in characters
+    mean_length: float = 0.0
+    # Number of tokens containing the space marker
+    num_with_space_marker: int = 0
+    # Number of tokens containing non-ASCII characters
+    num_non_ascii: int = 0
+    # The longest tokens as (token id, token), longest first
+    longest_tokens: list[tuple[int, str]] = field(default_factory=list)
+
+
+@dataclass
+class VocabIssue:
+    # What makes the tokens near-duplicates
+    kind: str = ""
+    # The form the tokens share (casefolded, NFKC-normalized or stripped of spaces)
+    key: str = ""
+    # Ids of the tokens, ascending
+    token_ids: list[int] = field(default_factory=list)
+    # Decoded text of each token, in the order of token_ids
+    tokens: list[str] = field(default_factory=list)
+
+
+@dataclass
+class BinaryDatasetStats:
+    # Number of documents written
+    num_documents: int = 0
+    # Number of token ids written, including eos tokens
+    num_tokens: int = 0
+    # Id type of the binary file : "uint16" or "uint32"
+    dtype: str = "uint16"
+    # Path of the uint64 document offsets sidecar
+    index_path: str = ""
+
+
+@dataclass
+class MemoryReport:
+    # Bytes held by the id -> token dict, including the token strings
+    vocab_bytes: int = 0
+    # Bytes held by the token -> id dict, excluding strings and ids shared with vocab
+    inverse_vocab_bytes: int = 0
+    # Bytes held by the merge table
+    merges_bytes: int = 0
+    # Bytes held by lazily built caches such as the vocabulary trie
+    caches_bytes: int = 0
+    # Bytes held by the word table kept from the latest training run
+    training_state_bytes: int = 0
+    # Sum of all the above
+    total_bytes: int = 0
+
+
+@dataclass
+class RoundTripMismatch:
+    # 1-based line number in the corpus
+    line_number: int = 0
+    # The original line
+    original: str = ""
+    # The line after encode and decode
+    decoded: str = ""
+    # Character offset of the first difference
+    offset: int = 0
+    # UTF-8 byte offset of the first difference
+    byte_offset: int = 0
+    # Readable list of the differing spans, e.g. "replace 'ä' -> 'a' at 3"
+    diff: list[str] = field(default_factory=list)
+
+
+@dataclass
+class ValidationReport:
+    # Number of lines checked
+    num_lines: int = 0
+    # Number of lines that did not round-trip
+    num_mismatches: int = 0
+    # Details of the first mismatching lines
+    mismatches: list[RoundTripMismatch] = field(default_factory=list)
+
+    @property
+    def ok(self) -> bool:
+        return self.num_mismatches == 0
+
+
+@dataclass
+class DivergentText:
+    # 1-based line number in the corpus
+    line_number: int = 0
+    # The text
+    text: str = ""
+    # 1 - shared token boundaries / all token boundaries (0 : same segmentation, 1 : no boundary in common)
+    divergence: float = 0.0
+    # The text spans of the first tokenizer's tokens
+    pieces_a: list[str] = field(default_factory=list)
+    # The text spans of the second tokenizer's tokens
+    pieces_b: list[str] = field(default_factory=list)
+
+
+@dataclass
+class ComparisonReport:
+    # Number of lines encoded by both tokenizers
+    num_lines: int = 0
+    # Number of lines that failed to encode with either tokenizer (e.g. characters missing from a vocabulary)
+    num_failed: int = 0
+    # UTF-8 bytes of the encoded lines
+    num_bytes: int = 0
+    # Tokens produced by each tokenizer
+    num_tokens_a: int = 0
+    num_tokens_b: int = 0
+    # Tokens per UTF-8 byte of each tokenizer (lower is better compression)
+    tokens_per_byte_a: float = 0.0
+    tokens_per_byte_b: float = 0.0
+    # Vocabulary sizes, special tokens included
+    vocab_size_a: int = 0
+    vocab_size_b: int = 0
+    # Number of regular tokens decoding to the same bytes in both vocabularies
+    shared_tokens: int = 0
+    # Shared tokens over the distinct regular tokens of both vocabularies
+    vocab_overlap: float = 0.0
+    # The lines segmented most differently, most divergent first
+    examples: list[DivergentText] = field(default_factory=list)
+
+
+@dataclass
+class TokenExplanation:
+    # The token id
+    token_id: int = 0
+    # The token in vocabulary form (e.g. "Ġworld")
+    token: str = ""
+    # The text the token decodes to (e.g. " world")
+    surface: str = ""
+    # (start, end) character span of the token in the input text
+    offsets: tuple[int, int] = (0, 0)
+    # Merges that built the token, innermost first, e.g. ["w + o -> wo", "wo + rld -> world"]
+    merge_chain: list[str] = field(default_factory=list)
+    # Rank (0-based learning order) of the merge producing the token, None for base characters and special tokens
+    rank: int | None = None
+    # Whether the token is a special token matched through allowed_special
+    special: bool = False
+
+
+@dataclass
+class NgramStats:
+    # Most frequent character n-grams of each order, as (n-gram, count) pairs, most frequent first
+    char_ngrams: dict[int, list[tuple[str, int]]] = field(default_factory=dict)
+    # Most frequent word n-grams (words joined by a space) of each order, most frequent first
+    word_ngrams: dict[int, list[tuple[str, int]]] = field(default_factory=dict)
+
+
+@dataclass
+class MergeStep:
+    # Pair of token ids merged
+    pair: tuple[int, int] = (0, 0)
+    # Id of the merged token
+    new_id: int = 0
+    # Rank (0-based learning order) of the merge
+    rank: int = 0
+    # Index of the pair's left token in the symbol sequence at the time of the merge
+    position: int = 0
+    # Round (0-based) in which the merge was applied, one round per merged pair in rank order
+    merge_pass: int = 0
+
+
+@dataclass
+class MergeConflict:
+    # The added (special) token the merge collides with
+    token: str = ""
+    # Pair of token ids of the removed merge
+    pair: tuple[int, int] = (0, 0)
+    # Id of the token the merge produced
+    new_id: int = 0
+    # Regular tokens removed because of the merge, as no remaining merge produces them
+    removed_ids: list[int] = field(default_factory=list)
+
+
+@dataclass
+class ReferenceMismatch:
+    # Index of the case in the reference file
+    index: int = 0
+    # The input text of the case
+    text: str = ""
+    # The recorded token ids
+    expected: list[int] = field(default_factory=list)
+    # The token ids produced now, None if encoding failed
+    actual: list[int] | None = None
+    # Position of the first differing token id
+    first_difference: int = 0
+    # The error raised by encode, if any
+    error: str | None = None
+
+
+@dataclass
+class ReferenceReport:
+    # Number of cases checked
+    num_cases: int = 0
+    # Number of cases whose encoding changed
+    num_mismatches: int = 0
+    # Details of the first mismatching cases
+    mismatches: list[ReferenceMismatch] = field(default_factory=list)
+    # Whether the tokenizer fingerprint equals the recorded one, None if none was recorded
+    fingerprint_matches: bool | None = None
+
+    @property
+    def ok(self) -> bool:
+        return self.num_mismatches == 0
+
+
+@dataclass
+class ThroughputReport:
+    # UTF-8 size of the benchmark texts in bytes
+    num_bytes: int = 0
+    # Number of tokens produced for the benchmark texts
+    num_tokens: int = 0
+    # Encode speed in megabytes (10^6 bytes) of input per second
+    encode_mb_per_s: float = 0.0
+    # Encode speed in tokens produced per second
+    encode_tokens_per_s: float = 0.0
+    # Decode speed in megabytes of output per second
+    decode_mb_per_s: float = 0.0
+    # Decode speed in tokens consumed per second
+    decode_tokens_per_s: float = 0.0
+    # Training speed in megabytes of corpus per second, None if training was not measured
+    train_mb_per_s: float | None = None
+
+
+@dataclass
+class MetricsSnapshot:
+    # Number of texts encoded successfully
+    texts_encoded: int = 0
+    # Number of token ids produced by those encodes
+    tokens_produced: int = 0
+    # Number of failed encodes by error code (e.g. {"disallowed_special_token": 2})
+    errors: dict[str, int] = field(default_factory=dict)
+    # Pre-tokens found whole in the vocabulary, encoded without applying merges
+    cache_hits: int = 0
+    # Pre-tokens encoded by applying merges
+    cache_misses: int = 0
+
+    @property
+    def cache_hit_rate(self) -> float:
+        lookups = self.cache_hits + self.cache_misses
+        return self.cache_hits / lookups if lookups else 0.0
@@ -0,0 +1,66 @@
+import re
+
+# Lowercased abbreviations (without the final period) that do not end a sentence
+DEFAULT_ABBREVIATIONS = frozenset({
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "cf", "al",
+    "fig", "no", "vol", "approx", "dept", "inc", "ltd", "co", "corp", "jan", "feb", "mar", "apr",
+    "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec", "u.s", "a.m", "p.m",
+})
+
+# Candidate boundary : terminal punctuation with optional closing quotes/brackets, or a blank line
+BOUNDARY_PATTERN = re.compile(r"[.!?]+[\"')\]’”]*(?=\s|$)|\n\s*\n")
+
+# Word (possibly dotted, e.g. "e.g") directly before a period
+WORD_BEFORE_PERIOD = re.compile(r"([\w.]+)\.$")
+
+# First non-space character after a boundary
+NEXT_CHAR = re.compile(r"\s*(\S)")
+
+
+def split_sentences(text: str, abbreviations: set[str] | None = None) -> list[tuple[str, tuple[int, int]]]:
+    """
+    Split text into sentences with simple punctuation rules
+
+    A sentence ends at ".", "!" or "?" (plus closing quotes or brackets) followed by
+    whitespace, or at a blank line. A period does not end a sentence after a known
+    abbreviation, a single-letter initial, or when the next word starts in lowercase.
+
+    Args:
+        text (str) : The input text
+        abbreviations (set[str] or None) : Lowercased abbreviations without the final period,
+            defaults to DEFAULT_ABBREVIATIONS
+
+    Returns:
+        list[tuple[str, tuple[int, int]]] : Each sentence with its (start, end) character span,
+            surrounding whitespace excluded
+    """
+
+    abbreviations = DEFAULT_ABBREVIATIONS if abbreviations is None else abbreviations
+    sentences = []
+    start = 0
+    for match in BOUNDARY_PATTERN.finditer(text):
+        end = match.end()
+        if match.group(0).startswith("."):
+            word = WORD_BEFORE_PERIOD.search(text, max(start, match.start() - 64), match.start() + 1)
+            following = NEXT_CHAR.match(text, end)
+            if word is not None:
+                token = word.group(1).lower()
+                if token in abbreviations or (len(token) == 1 and token.isalpha()):
+                    continue
+            if following is not None and following.group(1).islower():
+                continue
+        _append_sentence(text, start, end, sentences)
+        start = end
+
+    _append_sentence(text, start, len(text), sentences)
+    return sentences
+
+
+def _append_sentence(text: str, start: int, end: int, sentences: list[tuple[str, tuple[int, int]]]) -> None:
+    # Trim surrounding whitespace and skip empty sentences
+    while start < end and text[start].isspace():
+        start += 1
+    while end > start and text[end - 1].isspace():
+        end -= 1
+    if start < end:
+        sentences.append((text[start:end], (start, end)))
@@ -0,0 +1,86 @@
+import struct
+
+# SentencePiece marker for a preceding space
+SP_SPACE = "▁"
+
+# ModelProto.SentencePiece.Type values
+PIECE_NORMAL = 1
+PIECE_UNKNOWN = 2
+PIECE_CONTROL = 3
+PIECE_UNUSED = 5
+
+# TrainerSpec.ModelType values
+MODEL_TYPES = {"unigram" : 1, "bpe" : 2}
+
+# Protobuf wire types
+WIRE_VARINT = 0
+WIRE_LENGTH = 2
+WIRE_FIXED32 = 5
+
+
+def _varint(value: int) -> bytes:
+    # Negative int32 values are encoded as 64-bit two's complement
+    value &= (1 << 64) - 1
+    data = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value:
+            data.append(byte | 0x80)
+        else:
+            data.append(byte)
+            return bytes(data)
+
+
+def _field(number: int, wire_type: int, payload: bytes) -> bytes:
+    key = _varint(number << 3 | wire_type)
+    if wire_type == WIRE_LENGTH:
+        return key + _varint(len(payload)) + payload
+    return key + payload
+
+
+def _int(number: int, value: int) -> bytes:
+    return _field(number, WIRE_VARINT, _varint(value))
+
+
+def _string(number: int, value: str) -> bytes:
+    return _field(number, WIRE_LENGTH, value.encode("utf-8", "surrogatepass"))
+
+
+def _float(number: int, value: float) -> bytes:
+    return _field(number, WIRE_FIXED32, struct.pack("<f", value))
+
+
+def build_model_proto(pieces: list[tuple[str, float, int]], model_type: str, special_ids: dict[str, int]) -> bytes:
+    """
+    Serialize a SentencePiece ModelProto (the content of a `.model` file)
+
+    Only the fields needed to load and run the model are written : the pieces, the
+    trainer spec (model type, vocabulary size, special ids) and an identity normalizer
+    that collapses extra whitespace and does not add a dummy prefix.
+
+    Args:
+        pieces (list[tuple[str, float, int]]) : (piece, score, type) of every id, in id order
+        model_type (str) : "bpe" or "unigram"
+        special_ids (dict[str, int]) : Id of the "unk" piece and, if any, of the "bos", "eos" and "pad" pieces
+
+    Returns:
+        bytes : The serialized ModelProto
+    """
+
+    if model_type not in MODEL_TYPES:
+        raise ValueError(f"Invalid model type {model_type}. Choose one of {sorted(MODEL_TYPES)}")
+
+    data = bytearray()
+    for piece, score, piece_type in pieces:
+        data += _field(1, WIRE_LENGTH, _string(1, piece) + _float(2, score) + _int(3, piece_type))
+
+    trainer_spec = _int(3, MODEL_TYPES[model_type]) + _int(4, len(pieces))
+    # Unset roles are written as -1, as SentencePiece does
+    for role, number in (("unk", 40), ("bos", 41), ("eos", 42), ("pad", 43)):
+        trainer_spec += _int(number, special_ids.get(role, -1))
+    data += _field(2, WIRE_LENGTH, trainer_spec)
+
+    normalizer_spec = _string(1, "identity") + _int(3, False) + _int(4, True) + _int(5, True)
+    data += _field(3, WIRE_LENGTH, normalizer_spec)
+    return bytes(data)
@@ -0,0 +1,135 @@
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+import json
+
+from .errors import LucidError
+from .tokenizer import BPETokenizer
+
+# Largest accepted request body, in bytes
+MAX_BODY_BYTES = 16 * 1024 * 1024
+
+
+class TokenizerRequestHandler(BaseHTTPRequestHandler):
+    # Set on the subclass created by make_server
+    tokenizer: BPETokenizer = None
+
+    def do_GET(self):
+        if self.path != "/info":
+            return self._send(404, {"error" : f"Unknown endpoint {self.path}"})
+
+        tokenizer = self.tokenizer
+        self._send(200, {
+            "vocab_size" : len(tokenizer.vocab),
+            "num_merges" : len(tokenizer.bpe_merges),
+            "fingerprint" : tokenizer.fingerprint(),
+            "special_tokens" : {token : tokenizer.inverse_vocab[token] for token in sorted(tokenizer.special_tokens())},
+            "config" : tokenizer.config.to_dict(),
+        })
+
+
+    def do_POST(self):
+        handlers = {"/encode" : self._encode, "/decode" : self._decode, "/count_tokens" : self._count_tokens}
+        if self.path not in handlers:
+            return self._send(404, {"error" : f"Unknown endpoint {self.path}"})
+
+        try:
+            length = int(self.headers.get("Content-Length", 0))
+        except ValueError:
+            return self._send(400, {"error" : "Content-Length must be an integer"})
+        if length < 0:
+            return self._send(400, {"error" : "Content-Length must not be negative"})
+        if length > MAX_BODY_BYTES:
+            return self._send(413, {"error" : f"Request body larger than {MAX_BODY_BYTES} bytes"})
+        try:
+            request = json.loads(self.rfile.read(length))
+            if not isinstance(request, dict):
+                raise ValueError("The request body must be a JSON object")
+            self._send(200, handlers[self.path](request))
+        except LucidError as error:
+            self._send(400, {"error" : error.message, "code" : error.code})
+        except (ValueError, KeyError, TypeError) as error:
+            self._send(400, {"error" : str(error)})
+
+
+    def _encode(self, request: dict) -> dict:
+        # {"text" : str} or {"texts" : [str, ...]}, with optional allowed_special / disallowed_special
+        allowed_special = _special(request.get("allowed_special"))
+        disallowed_special = _special(request.get("disallowed_special"))
+        if "texts" in request:
+            return {"ids" : [self.tokenizer.encode(text, allowed_special, disallowed_special) for text in _texts(request)]}
+        return {"ids" : self.tokenizer.encode(_text(request), allowed_special, disallowed_special)}
+
+
+    def _decode(self, request: dict) -> dict:
+        # {"ids" : [int, ...]} or {"batch" : [[int, ...], ...]}
+        if "batch" in request:
+            return {"texts" : self.tokenizer.decode_batch(request["batch"])}
+        return {"text" : self.tokenizer.decode(request["ids"])}
+
+
+    def _count_tokens(self, request: dict) -> dict:
+        # Same input as /encode, answers with token counts only
+        allowed_special = _special(request.get("allowed_special"))
+        disallowed_special = _special(request.get("disallowed_special"))
+        if "texts" in request:
+            return {"counts" : [len(self.tokenizer.encode(text, allowed_special, disallowed_special))
+                                for text in _texts(request)]}
+        return {"count" : len(self.tokenizer.encode(_text(request), allowed_special, disallowed_special))}
+
+
+    def _send(self, status: int, body: dict) -> None:
+        data = json.dumps(body, ensure_ascii=False).encode("utf-8")
+        self.send_response(status)
+        self.send_header("Content-Type", "application/json; charset=utf-8")
+        self.send_header("Content-Length", str(len(data)))
+        self.end_headers()
+        self.wfile.write(data)
+
+
+    def log_message(self, format: str, *args) -> None:
+        # Request logging is left to a reverse proxy
+        pass
+
+
+def _text(request: dict) -> str:
+    if not isinstance(request.get("text"), str):
+        raise ValueError('Expected a "text" string or a "texts" list')
+    return request["text"]
+
+
+def _texts(request: dict) -> list[str]:
+    texts = request["texts"]
+    if not isinstance(texts, list) or not all(isinstance(text, str) for text in texts):
+        raise ValueError('"texts" must be a list of strings')
+    return texts
+
+
+def _special(value) -> set[str] | str | None:
+    # JSON lists become sets, "all" / "none" / null pass through
+    return set(value) if isinstance(value, list) else value
+
+
+def make_server(tokenizer: BPETokenizer, host: str = "127.0.0.1", port: int = 8000) -> ThreadingHTTPServer:
+    """
+    Create an HTTP server exposing a tokenizer as a JSON service
+
+    Endpoints :
+        POST /encode : {"text" : str} -> {"ids" : [...]}, or {"texts" : [...]} -> {"ids" : [[...], ...]}
+        POST /decode : {"ids" : [...]} -> {"text" : str}, or {"batch" : [[...], ...]} -> {"texts" : [...]}
+        POST /count_tokens : {"text" : str} -> {"count" : int}, or {"texts" : [...]} -> {"counts" : [...]}
+        GET /info : vocabulary size, fingerprint, special tokens and config
+
+    Encoding and counting requests accept "allowed_special" and "disallowed_special" ("all", "none"
+    or a list of tokens). Errors are answered with status 400 and {"error" : message, "code" : code}.
+
+    Args:
+        tokenizer (BPETokenizer) : The trained tokenizer to serve
+        host (str) : Interface to bind, localhost by default
+        port (int) : Port to bind, 0 picking a free one
+
+    Returns:
+        ThreadingHTTPServer : The server, to run with serve_forever()
+    """
+
+    tokenizer._require_trained("serve")
+    handler = type("BoundTokenizerRequestHandler", (TokenizerRequestHandler,), {"tokenizer" : tokenizer})
+    return ThreadingHTTPServer((host, port), handler)
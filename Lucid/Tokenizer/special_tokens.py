@@ -0,0 +1,59 @@
+from dataclasses import asdict, dataclass
+
+# Named roles a special token can take
+SPECIAL_TOKEN_ROLES = ("bos", "eos", "pad", "unk")
+
+
+@dataclass
+class SpecialTokensMap:
+    # Beginning-of-sequence token
+    bos_token: str | None = None
+    # End-of-sequence token
+    eos_token: str | None = None
+    # Padding token
+    pad_token: str | None = None
+    # Token substituted for characters missing from the vocabulary
+    unk_token: str | None = None
+
+
+    def get(self, role: str) -> str | None:
+        """
+        Get the token assigned to a role
+
+        Args:
+            role (str) : One of "bos", "eos", "pad", "unk"
+
+        Returns:
+            str or None : The token, None if the role is unset
+        """
+
+        self.check_role(role)
+        return getattr(self, f"{role}_token")
+
+
+    def set(self, role: str, token: str | None) -> None:
+        """
+        Assign a token to a role
+
+        Args:
+            role (str) : One of "bos", "eos", "pad", "unk"
+            token (str or None) : The token, None to unset the role
+        """
+
+        self.check_role(role)
+        setattr(self, f"{role}_token", token)
+
+
+    def to_dict(self) -> dict[str, str | None]:
+        return asdict(self)
+
+
+    @classmethod
+    def from_dict(cls, data: dict[str, str | None]) -> "SpecialTokensMap":
+        return cls(**{key : value for key, value in data.items() if key in cls.__dataclass_fields__})
+
+
+    @staticmethod
+    def check_role(role: str) -> None:
+        if role not in SPECIAL_TOKEN_ROLES:
+            raise ValueError(f"Invalid special token role {role}. Choose one of {SPECIAL_TOKEN_ROLES}")
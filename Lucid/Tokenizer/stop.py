@@ -0,0 +1,103 @@
+from dataclasses import dataclass
+
+from .matching import AhoCorasick
+
+
+@dataclass
+class StopHit:
+    # The stop string that was matched
+    stop_sequence: str
+    # Index (in the fed stream) of the token that completed the match, None when matching plain text
+    token_index: int | None
+    # Character offset of the match start in the decoded stream
+    start: int
+    # Character offset just past the match end in the decoded stream
+    end: int
+
+
+class StopMatcher:
+
+    def __init__(self, tokenizer, stop_sequences: list[str]):
+        """
+        Incremental stop-sequence matcher over a stream of token IDs
+
+        Tokens are decoded incrementally, so the matched text and offsets are those of decode
+        over the whole stream, characters split across byte-level tokens included.
+
+        Args:
+            tokenizer (BPETokenizer) : The tokenizer used to render token IDs as text
+            stop_sequences (list[str]) : The stop strings to detect
+        """
+
+        self.tokenizer = tokenizer
+        self.stop_sequences = list(dict.fromkeys(stop_sequences))
+        self.automaton = AhoCorasick(self.stop_sequences)
+        self.reset()
+
+
+    def reset(self) -> None:
+        """
+        Forget all previously fed tokens
+        """
+
+        self.state = 0
+        self.tokens_fed = 0
+        self.chars_fed = 0
+        self.decoder = self.tokenizer.stream_decoder()
+
+
+    @property
+    def pending_length(self) -> int:
+        """
+        Number of trailing characters that are a prefix of some stop string.
+        A server should hold these back until the next token resolves them.
+        """
+
+        return self.automaton.depth[self.state]
+
+
+    def feed(self, token_id: int) -> StopHit | None:
+        """
+        Feed the next generated token
+
+        Args:
+            token_id (int) : The generated token ID
+
+        Returns:
+            StopHit or None : The earliest stop string completed by this token
+        """
+
+        hit = None
+        for char in self.decoder.feed(token_id):
+            self.state = self.automaton.step(self.state, char)
+            self.chars_fed += 1
+            if hit is None and self.automaton.output[self.state]:
+                # Prefer the longest stop string ending at this character
+                index = max(self.automaton.output[self.state], key=lambda i: len(self.stop_sequences[i]))
+                stop_sequence = self.stop_sequences[index]
+                hit = StopHit(stop_sequence, self.tokens_fed, self.chars_fed - len(stop_sequence), self.chars_fed)
+
+        self.tokens_fed += 1
+        return hit
+
+
+def find_stop(text: str, stop_sequences: list[str]) -> StopHit | None:
+    """
+    Find the stop string that completes first in a text
+
+    Args:
+        text (str) : The text to search
+        stop_sequences (list[str]) : The stop strings
+
+    Returns:
+        StopHit or None : The earliest-ending match (the longest one on ties), without a token index
+    """
+
+    stop_sequences = list(dict.fromkeys(stop_sequences))
+    best = None
+    for start, end, index in AhoCorasick(stop_sequences).find_all(text):
+        if best is None or end < best.end or (end == best.end and start < best.start):
+            best = StopHit(stop_sequences[index], None, start, end)
+        elif end > best.end:
+            break
+    return best
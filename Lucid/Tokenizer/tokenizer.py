@@ -1,257 +1,4684 @@
 from collections import Counter, deque
+from concurrent.futures import Executor, ProcessPoolExecutor
+from concurrent.futures.process import BrokenProcessPool
+from dataclasses import asdict, dataclass, field
+from typing import IO, Callable, Iterable, Iterator
+import array
+import asyncio
+import bisect
+import codecs
+import difflib
+import dataclasses
+import functools
+import hashlib
+import heapq
 import json
+import math
+import os
+import random
 import re
+import sys
+import threading
+import unicodedata
+import warnings
+import weakref
+
+from .bktree import BKTree
+from .builder import TokenizerBuilder
+from .config import (FINGERPRINT_DEFAULTED_FIELDS, BinaryDatasetConfig, BlankInputPolicy, ChatTemplateConfig, CorpusFormat,
+                     EncodeLimits, EncodeStrategy, FrequencyMode, InvalidUtf8Policy, PreTokenizer, SpecialTokenPolicy, TokenDtype,
+                     TokenizerConfig, TrainerConfig, VisualizationFormat, VocabIssueKind, VocabMergeStrategy, WordBoundary)
+from .corpus import count_ngrams, dedupe_text, filter_text, iter_documents, iter_jsonl_field
+from .embedded import pack_artifact, unpack_artifact
+from .encoding import Encoding, PackedBatch, Segment
+from .errors import (LucidError, LucidBlankInput, LucidCharacterNotFound, LucidDisallowedSpecialToken, LucidEncodeLimitExceeded,
+                     LucidFingerprintMismatch, LucidFrozen, LucidInvalidConfig, LucidInvalidMerge, LucidLanguageTagNotSet,
+                     LucidNotTrained, LucidPostProcessorNotSet, LucidSpecialTokenNotFound, LucidSpecialTokenNotSet,
+                     LucidTokenGroupNotSet, LucidTokenIdNotFound)
+from .features import require_feature
+from .fileio import FilePath, PathOrFile, fs_path, open_binary, open_text, prepare_write_path
+from .gguf import TOKEN_TYPE_CONTROL, TOKEN_TYPE_NORMAL, TOKEN_TYPE_UNKNOWN, TOKEN_TYPE_UNUSED, write_gguf_vocab
+from .matching import AhoCorasick
+from .memory import deep_sizeof
+from .metrics import CACHE_HITS, CACHE_MISSES, ENCODE_ERRORS, TEXTS_ENCODED, TOKENS_PRODUCED, InMemoryMetrics, Metrics
+from .parallel import default_num_workers, imap_ordered, parallel_map
+from .processors import TemplateProcessor
+from .reports import (BinaryDatasetStats, ComparisonReport, CorpusEncodeStats, CoverageReport, DivergentText, EvalMetrics,
+                      MemoryReport, MergeConflict, MergeStep, MetricsSnapshot, NgramStats, ReferenceMismatch, ReferenceReport,
+                      RoundTripMismatch, TokenExplanation, ValidationReport, VocabIssue, VocabStats)
+from .segment import split_sentences
+from .sentencepiece import PIECE_CONTROL, PIECE_NORMAL, PIECE_UNKNOWN, PIECE_UNUSED, SP_SPACE, build_model_proto
+from .special_tokens import SpecialTokensMap
+from .stop import StopHit, StopMatcher, find_stop
+from .trie import VocabTrie
+from .visualize import render_tokens
+
+
+@dataclass
+class VocabSizeSuggestion:
+    # (vocab_size, tokens_per_char) measured at each checkpoint
+    curve: list[tuple[int, float]] = field(default_factory=list)
+    # Smallest checkpointed vocab size meeting the target, None if never reached
+    suggested_vocab_size: int | None = None
+
+
+@dataclass
+class TrainingStats:
+    # Number of near-duplicate lines/paragraphs skipped by deduplication
+    duplicates_skipped: int = 0
+    # Number of corpus lines dropped by the TrainerConfig filters
+    lines_filtered: int = 0
+    # Number of distinct rare characters left out of the vocabulary by TrainerConfig.character_coverage
+    chars_dropped: int = 0
+    # Number of JSON Lines records skipped by train_from_files for being malformed or lacking the text field
+    records_skipped: int = 0
+    # Most frequent character and word n-grams of the corpus, with TrainerConfig.collect_ngrams
+    ngrams: NgramStats | None = None
+    # Number of learned merges attributed to each language, with TrainerConfig.language_budgets
+    merges_per_language: dict[str, int] | None = None
+
+
+# Casing markers used when TokenizerConfig.case_markers is enabled
+CAP_TOKEN = "<|cap|>"
+UPPER_TOKEN = "<|upper|>"
+
+# Invisible character inserted by escape_special_tokens after the first character of each special token (zero width space)
+ESCAPE_CHAR = "\u200b"
+UNESCAPE_PATTERN = re.compile(ESCAPE_CHAR + "(" + ESCAPE_CHAR + "?)")
+
+# Suffix of the last symbol of every word with WordBoundary.END
+END_OF_WORD = "</w>"
+
+# array.array typecodes of each token id type
+ARRAY_TYPECODES = {
+    TokenDtype.UINT16 : "H",
+    TokenDtype.UINT32 : "I" if array.array("I").itemsize == 4 else "L",
+}
+
+# File name of the tokenizer artifact in a Hugging Face Hub repository
+HUB_FILENAME = "lucid_tokenizer.json"
+
+# Processed text larger than this is split into chunks counted in parallel
+PARALLEL_CHUNK_CHARS = 1 << 20
+
+# Tokenizer used by encode_corpus workers, set once per process by set_worker_tokenizer
+_WORKER_TOKENIZER = None
+
+# Tokenizers loaded with load_shared, keyed by the hash of the artifact
+_SHARED_ARTIFACTS = {}
+_SHARED_LOCK = threading.Lock()
+
+# Batches holding more token ids than this are decoded in parallel
+PARALLEL_DECODE_TOKENS = 1 << 18
+
+# Code pre-tokens : line indentation, newline runs, identifiers, numbers, operator runs and other whitespace
+CODE_PATTERN = re.compile(r"^[^\S\n]+|\n+| ?[^\W\d]\w*| ?\d+| ?[^\w\s]+|[^\S\n]+(?!\S)|[^\S\n]+", re.MULTILINE)
+
+
+def count_pre_tokens(text: str, config: TokenizerConfig) -> Counter:
+    """
+    Count pre-token frequencies in a chunk of raw training text
+
+    Args:
+        text (str) : The raw text chunk
+        config (TokenizerConfig) : Settings selecting the pre-tokenizer
+
+    Returns:
+        Counter : Mapping of pre-token to frequency
+    """
+
+    tokenizer = BPETokenizer(config)
+    if config.pre_tokenizer == PreTokenizer.CODE:
+        return Counter(token for token, _, _ in tokenizer.pre_tokenize(text) if token not in (CAP_TOKEN, UPPER_TOKEN))
+
+    # Whitespace pre-tokens only depend on the word and on whether it starts the text,
+    # so raw words are counted first and each distinct word is pre-tokenized once
+    lines = text.split("\n")
+    counts = Counter({"\n" : len(lines) - 1})
+    for word, freq in Counter(word for line in lines for word in line.split()).items():
+        # Pre-tokens of the word after a newline, which carry the space marker
+        for token, _, _ in tokenizer.pre_tokenize("\n" + word)[1:]:
+            counts[token] += freq
+
+    # The very first word of the text has no space marker
+    first_word = next(iter(lines[0].split()), None)
+    if first_word is not None:
+        for token, _, _ in tokenizer.pre_tokenize("\n" + first_word)[1:]:
+            counts[token] -= 1
+        for token, _, _ in tokenizer.pre_tokenize(first_word):
+            counts[token] += 1
+
+    for token in (CAP_TOKEN, UPPER_TOKEN):
+        del counts[token]
+    return +counts
+
+
+def count_documents(documents: list[str], count_chunk: Callable[[str], Counter]) -> Counter:
+    """
+    Count the words of several documents separately and sum the counts (picklable helper for parallel training)
+
+    Args:
+        documents (list[str]) : The documents or chunks of text
+        count_chunk (callable) : Function counting the words of a single document
+
+    Returns:
+        Counter : Mapping of word to frequency over all documents
+    """
+
+    counts = Counter()
+    for document in documents:
+        counts.update(count_chunk(document))
+    return counts
+
+
+def count_shard(path: str, config: TokenizerConfig, trainer_config: TrainerConfig,
+                jsonl_field: str | None = None) -> tuple[Counter, "TrainingStats", dict | None]:
+    """
+    Count the training words of one shard file (picklable helper for train_from_shards)
+
+    Args:
+        path (str) : Path of the shard, optionally .gz, .xz or .zst
+        config (TokenizerConfig) : Settings of the tokenizer being trained
+        trainer_config (TrainerConfig) : The training settings
+        jsonl_field (str or None) : Field of the JSON Lines records to read, None for plain text
+
+    Returns:
+        tuple[Counter, TrainingStats, dict or None] : Mapping of word to frequency, the filtering statistics of the
+            shard and, with trainer_config.collect_ngrams, its n-gram counts (see count_ngrams)
+    """
+
+    stats = TrainingStats()
+    skipped = []
+    with open_text(path, "r") as file:
+        text = file.read() if jsonl_field is None else "\n".join(iter_jsonl_field(file, jsonl_field, skipped))
+    stats.records_skipped = len(skipped)
+    ngram_counts = {} if trainer_config.collect_ngrams else None
+    # The shards are already spread over the workers : count each one in a single process
+    word_counts = BPETokenizer(config)._count_training_words(text, trainer_config, stats, num_workers=1,
+                                                             ngram_counts=ngram_counts)
+    return word_counts, stats, ngram_counts
+
+
+def byte_text(text: str) -> str:
+    """
+    Represent the UTF-8 bytes of a text as characters chr(0)..chr(255), as used in byte-level mode
+
+    Args:
+        text (str) : The input text
+
+    Returns:
+        str : One character per UTF-8 byte
+    """
+
+    return text.encode("utf-8", "surrogatepass").decode("latin-1")
+
+
+def encode_texts(tokenizer: "BPETokenizer", texts: list[str], allowed_special: set[str] | str | None = None,
+                 disallowed_special: set[str] | str | None = None) -> list[list[int]]:
+    """
+    Encode a chunk of texts (picklable helper for parallel batch encoding)
+
+    Args:
+        tokenizer (BPETokenizer) : The tokenizer to use
+        texts (list[str]) : The input texts to encode
+        allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+        disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the text
+
+    Returns:
+        list[list[int]] : The token IDs of each text
+    """
+
+    return [tokenizer.encode(text, allowed_special, disallowed_special) for text in texts]
+
+
+def encode_plus_texts(tokenizer: "BPETokenizer", texts: list[str], allowed_special: set[str] | str | None = None,
+                      disallowed_special: set[str] | str | None = None) -> list[Encoding]:
+    """
+    Encode a chunk of texts into Encoding objects (picklable helper for parallel batch encoding)
+
+    Args:
+        tokenizer (BPETokenizer) : The tokenizer to use
+        texts (list[str]) : The input texts to encode
+        allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+        disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the text
+
+    Returns:
+        list[Encoding] : The encoding of each text
+    """
+
+    return [tokenizer.encode_plus(text, allowed_special, disallowed_special) for text in texts]
+
+
+def set_worker_tokenizer(tokenizer: "BPETokenizer") -> None:
+    """
+    Install the tokenizer used by encode_corpus_chunk and map_worker_chunk (process pool initializer)
+
+    Args:
+        tokenizer (BPETokenizer) : The tokenizer to use in this process
+    """
+
+    global _WORKER_TOKENIZER
+    _WORKER_TOKENIZER = tokenizer
+
+
+def encode_corpus_chunk(lines: list[str], format: CorpusFormat, allowed_special: set[str] | str | None,
+                        dtype: TokenDtype) -> tuple[bytes, int, int]:
+    """
+    Encode a chunk of corpus lines with the tokenizer installed by set_worker_tokenizer (picklable helper for encode_corpus)
+
+    Args:
+        lines (list[str]) : The lines, without their line endings
+        format (CorpusFormat) : The output format
+        allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+        dtype (TokenDtype) : Id type of the BIN format
+
+    Returns:
+        tuple[bytes, int, int] : The UTF-8 (JSONL, TEXT) or binary output of the chunk, its number of lines and of tokens
+    """
+
+    return encode_corpus_lines(_WORKER_TOKENIZER, lines, format, allowed_special, dtype)
+
+
+def encode_corpus_lines(tokenizer: "BPETokenizer", lines: list[str], format: CorpusFormat,
+                        allowed_special: set[str] | str | None, dtype: TokenDtype) -> tuple[bytes, int, int]:
+    """
+    Encode a chunk of corpus lines into their serialized output
+
+    Args:
+        tokenizer (BPETokenizer) : The tokenizer to use
+        lines (list[str]) : The lines, without their line endings
+        format (CorpusFormat) : The output format
+        allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+        dtype (TokenDtype) : Id type of the BIN format
+
+    Returns:
+        tuple[bytes, int, int] : The UTF-8 (JSONL, TEXT) or binary output of the chunk, its number of lines and of tokens
+    """
+
+    sequences = [tokenizer.encode(line, allowed_special) for line in lines]
+    num_tokens = sum(len(token_ids) for token_ids in sequences)
+    if format == CorpusFormat.JSONL:
+        return "".join(json.dumps(token_ids) + "\n" for token_ids in sequences).encode("utf-8"), len(lines), num_tokens
+    if format == CorpusFormat.TEXT:
+        return "".join(" ".join(map(str, token_ids)) + "\n" for token_ids in sequences).encode("utf-8"), len(lines), num_tokens
+
+    token_ids = array.array(ARRAY_TYPECODES[dtype], (token_id for sequence in sequences for token_id in sequence))
+    if sys.byteorder == "big":
+        token_ids.byteswap()
+    return token_ids.tobytes(), len(lines), num_tokens
+
+
+def decode_sequences(tokenizer: "BPETokenizer", sequences: list[list[int]]) -> list[str]:
+    """
+    Decode a chunk of token ID sequences (picklable helper for parallel batch decoding)
+
+    Args:
+        tokenizer (BPETokenizer) : The tokenizer to use
+        sequences (list[list[int]]) : The token ID sequences
+
+    Returns:
+        list[str] : The decoded text of each sequence
+    """
+
+    return [tokenizer.decode(token_ids) for token_ids in sequences]
+
+
+def map_worker_chunk(func: Callable, chunk: list, **kwargs) -> list:
+    """
+    Apply a chunk helper (e.g. encode_texts) with the tokenizer installed by set_worker_tokenizer
+
+    Args:
+        func (callable) : The chunk helper, called with the tokenizer, the chunk and kwargs
+        chunk (list) : The chunk of inputs
+
+    Returns:
+        list : The results of the chunk
+    """
+
+    return func(_WORKER_TOKENIZER, chunk, **kwargs)
+
+
+class StreamDecoder:
+
+    def __init__(self, tokenizer: "BPETokenizer"):
+        """
+        Decode token IDs one at a time, e.g. while they are generated
+
+        The pieces returned by feed, followed by flush, concatenate to decode(token_ids) : the
+        spacing of a token depends on the previous ones as in decode, and in byte-level mode the
+        bytes of a character split across tokens are held back until its last byte arrives.
+
+        Args:
+            tokenizer (BPETokenizer) : The tokenizer whose token IDs are decoded
+        """
+
+        tokenizer._require_trained("decode")
+        self.tokenizer = tokenizer
+        # Number of token IDs fed so far
+        self.tokens_fed = 0
+        # Last decoded character, used to space out newlines
+        self._last_char = ""
+        # Pending casing marker and whether the marked word has started
+        self._case_marker = None
+        self._word_started = False
+        # Whether the previous token ended a word (WordBoundary.END)
+        self._pending_space = False
+        # Incomplete UTF-8 sequence at the end of the bytes fed so far (byte-level mode)
+        self._utf8 = codecs.getincrementaldecoder("utf-8")(errors="replace") if tokenizer.config.byte_level else None
+
+
+    def feed(self, token_id: int) -> str:
+        """
+        Decode the next token ID
+
+        Args:
+            token_id (int) : The token ID
+
+        Returns:
+            str : The text completed by this token, possibly empty
+        """
+
+        piece = self.surface(token_id)
+        return piece if self._utf8 is None else self._utf8.decode(piece.encode("latin-1"))
+
+
+    def flush(self) -> str:
+        """
+        End the stream
+
+        Returns:
+            str : The held back bytes of an unfinished character, as U+FFFD
+        """
+
+        return "" if self._utf8 is None else self._utf8.decode(b"", final=True)
+
+
+    def surface(self, token_id: int) -> str:
+        # Surface text of the next token (one character per byte in byte-level mode)
+        tokenizer = self.tokenizer
+        config = tokenizer.config
+        if token_id not in tokenizer.vocab:
+            raise LucidTokenIdNotFound(token_id, self.tokens_fed)
+        self.tokens_fed += 1
+        token = tokenizer.vocab[token_id]
+        if config.case_markers:
+            if token in (CAP_TOKEN, UPPER_TOKEN):
+                self._case_marker, self._word_started = token, False
+                return ""
+            if self._word_started and tokenizer._starts_new_word(token):
+                self._case_marker = None
+            if self._case_marker is not None:
+                token, self._case_marker = tokenizer.apply_case(token, self._case_marker, tokenizer.space_marker)
+                self._word_started = True
+        if config.word_boundary == WordBoundary.END:
+            # The space after a word is only emitted once another word follows on the same line
+            piece = (" " if self._pending_space and token != "\n" else "") + token.removesuffix(END_OF_WORD)
+            self._pending_space = token.endswith(END_OF_WORD)
+        elif config.pre_tokenizer == PreTokenizer.CODE:
+            piece = token.replace(tokenizer.space_marker, " ")
+        elif token == "\n":
+            # Only the first newline of a run is spaced out from the preceding word
+            piece = " \n" if self._last_char and self._last_char not in " \n" else "\n"
+        elif token.startswith(tokenizer.space_marker):
+            piece = " " + token[1:]
+        else:
+            piece = token
+        if piece:
+            self._last_char = piece[-1]
+        return piece
+
 
 class BPETokenizer:
 
-    def __init__(self):
-        # Maps token id to token string
-        self.vocab = {}
-        # Maps token string to token id
-        self.inverse_vocab = {}
-        # Dictionary of BPE merges : {(token_id1, token_id2): merged_token_id}
-        self.bpe_merges = {}
+    def __init__(self, config: TokenizerConfig | None = None):
+        # Behavioral settings of the tokenizer
+        self.config = config or TokenizerConfig()
+        self.config.validate()
+        # Copy of the config as validated, to detect later in-place changes
+        self._validated_config = dataclasses.replace(self.config)
+        # Maps token id to token string
+        self.vocab = {}
+        # Maps token string to token id
+        self.inverse_vocab = {}
+        # Dictionary of BPE merges : {(token_id1, token_id2): merged_token_id}
+        self.bpe_merges = {}
+        # Special tokens assigned to named roles (bos, eos, pad, unk)
+        self.special_tokens_map = SpecialTokensMap()
+        # Optional template applied by encode_with_template
+        self.post_processor = None
+        # Language code -> language tag special token (e.g. "fr" -> "<|fr|>"), used by encode_with_lang
+        self.language_tags = {}
+        # Group name -> special tokens allowed or disallowed together at encode time (e.g. "tools")
+        self.token_groups = {}
+        # Token id -> occurrences in the training corpus segmented with every merge, recorded by training
+        self.token_frequencies = {}
+        # Input and output size limits enforced by every encode, None for no limits
+        self.encode_limits = None
+        # Receiver of the encode counters, None to disable them
+        self.metrics = None
+        # Lazily built prefix trie over the vocabulary
+        self._vocab_trie = None
+        # Lazily built trie over the decoded text of every token, used for stop sequences
+        self._stop_trie = None
+        # Special token matchers of each allowed_special set seen by encode
+        self._special_matchers = {}
+        # Lazily built set of special tokens, which ordinary text never encodes to
+        self._special_set = None
+        # Lazily built trie over the regular (non-special) tokens, used by EncodeStrategy.LONGEST_MATCH and OPTIMAL
+        self._match_trie = None
+        # Lazily built merge rank (1-based) of each merged token id, used to break EncodeStrategy.OPTIMAL ties
+        self._merge_ranks = None
+        # Lazily built rank (0-based learning order) of each merged pair, used by bpe_trace
+        self._pair_ranks = None
+        # Lazily built log-probability of each regular token, used by encode_sampled
+        self._sampling_scores = None
+        # Lazily built NUL-joined decoded text of every token, used for substring lookups
+        self._surface_text_index = None
+        # Lazily built BK-tree over the decoded text of the regular tokens, used by nearest_tokens
+        self._nearest_index = None
+        # Half-open [start, end) id ranges reserved for special tokens, skipped by training
+        self.reserved_id_ranges = []
+        # Word table, target size and settings of the latest training run, kept for checkpoints
+        self._training_state = None
+        # Digest of the frozen base tables, number of base merges and ids added since, None if not frozen
+        self._frozen = None
+        # Whether vocab, inverse_vocab and bpe_merges are shared with other instances (copied before mutation)
+        self._shared_tables = False
+        # Worker processes holding a copy of this tokenizer, started by the first large batch and reused until it changes
+        self._pool = None
+        # Number of workers and config the pool was started with
+        self._pool_key = None
+        # Guards the pool against concurrent batches starting one each
+        self._pool_lock = threading.RLock()
+
+
+    @classmethod
+    def builder(cls) -> TokenizerBuilder:
+        """
+        Start building a tokenizer, e.g. BPETokenizer.builder().vocab_size(8000).byte_level(True).train(corpus)
+
+        Returns:
+            TokenizerBuilder : A builder with default settings
+        """
+
+        return TokenizerBuilder(cls)
+
+
+    def _check_config_unchanged(self) -> None:
+        # Settings changed in place after construction bypassed validation
+        if self.config != self._validated_config:
+            warnings.warn("Changing tokenizer.config after construction is deprecated : "
+                          "pass a TokenizerConfig to the constructor or use BPETokenizer.builder()", DeprecationWarning, stacklevel=3)
+            self.config.validate()
+            self._validated_config = dataclasses.replace(self.config)
+
+
+    @property
+    def space_marker(self) -> str:
+        """
+        The character standing for a preceding space, a literal space when no marker is configured
+        """
+
+        return self.config.space_marker or " "
+
+
+    def __repr__(self) -> str:
+        roles = {role : token for role, token in self.special_tokens_map.to_dict().items() if token is not None}
+        return f"BPETokenizer(vocab_size={len(self.vocab)}, merges={len(self.bpe_merges)}, special_tokens={roles})"
+
+
+    def __len__(self) -> int:
+        return len(self.vocab)
+
+
+    def __getstate__(self) -> dict:
+        # Worker pools stay with the process that started them
+        state = self.__dict__.copy()
+        state["_pool"] = None
+        state["_pool_key"] = None
+        del state["_pool_lock"]
+        return state
+
+
+    def __setstate__(self, state: dict) -> None:
+        self.__dict__.update(state)
+        self._pool_lock = threading.RLock()
+
+
+    def __eq__(self, other: object) -> bool:
+        """
+        Compare the vocabularies and merges of two tokenizers
+
+        Tokenizers are mutable, so they are unhashable : use fingerprint() as a dictionary or cache key.
+        """
+
+        if not isinstance(other, BPETokenizer):
+            return NotImplemented
+        return self.vocab == other.vocab and self.bpe_merges == other.bpe_merges
+
+
+    # Equality follows the contents, which change in place
+    __hash__ = None
+
+
+    def __contains__(self, token: str) -> bool:
+        return token in self.inverse_vocab
+
+
+    def train(self, text: str, vocab_size: int, allowed_special: set[str] = {"<|endoftext|>"},
+              callback: Callable[[int, int], None] | None = None, config: TrainerConfig | None = None) -> TrainingStats:
+        """
+        Train BPE Tokenizer
+
+        Args:
+            text (str) : The text used to train the tokenizer
+            vocab_size (int) : The vocabulary size
+            allowed_special (set) : A set of included special tokens
+            callback (callable or None) : Called after each merge with the new token id and the corpus length in tokens
+            config (TrainerConfig or None) : Optional training settings
+
+        Returns:
+            TrainingStats : Statistics about the training run
+        """
+
+        self._check_not_frozen("train")
+        self._check_config_unchanged()
+        config = config or TrainerConfig()
+        if not 0.0 < config.character_coverage <= 1.0:
+            raise ValueError("character_coverage must be in (0, 1]")
+        stats = TrainingStats()
+        ngram_counts = {} if config.collect_ngrams else None
+        word_counts = self._count_training_words(text, config, stats, ngram_counts=ngram_counts)
+        if ngram_counts is not None:
+            self._report_ngrams(ngram_counts, config, stats)
+        self._train_on_word_counts(word_counts, vocab_size, allowed_special, callback, config, stats)
+        return stats
+
+
+    def train_from_shards(self, paths: list[FilePath], vocab_size: int, allowed_special: set[str] = {"<|endoftext|>"},
+                          callback: Callable[[int, int], None] | None = None, config: TrainerConfig | None = None,
+                          jsonl_field: str | None = None, num_workers: int | None = None,
+                          languages: list[str] | None = None) -> TrainingStats:
+        """
+        Train on a corpus split into shard files, in two phases
+
+        Phase 1 counts the words of every shard in its own worker process, so the corpus is never
+        held in memory as a whole, and sums the counts into one global word frequency table.
+        Phase 2 learns the merges from that table. The table does not depend on the order of the
+        shards or on the number of workers, so the result is identical for any of them.
+        Filters and deduplication (TrainerConfig.filters, dedupe) apply within each shard.
+
+        Tagging the shards with their language lets TrainerConfig.language_budgets bound the share
+        of the merges each language gets, so one language cannot crowd out the others.
+
+        Args:
+            paths (list[str, bytes or PathLike]) : Paths of the UTF-8 shard files, optionally .gz, .xz or .zst
+            vocab_size (int) : The vocabulary size
+            allowed_special (set) : A set of included special tokens
+            callback (callable or None) : Called after each merge with the new token id and the corpus length in tokens
+            config (TrainerConfig or None) : Optional training settings
+            jsonl_field (str or None) : Read the shards as JSON Lines and train on this field of every record
+                (see train_from_files)
+            num_workers (int or None) : Number of shards counted at once, defaults to TrainerConfig.num_workers
+            languages (list[str] or None) : Language of each shard (e.g. "en"), for TrainerConfig.language_budgets
+
+        Returns:
+            TrainingStats : Statistics about the training run, summed over the shards
+        """
+
+        self._check_not_frozen("train")
+        self._check_config_unchanged()
+        config = config or TrainerConfig()
+        if not 0.0 < config.character_coverage <= 1.0:
+            raise ValueError("character_coverage must be in (0, 1]")
+        if languages is not None and len(languages) != len(paths):
+            raise ValueError(f"Got {len(languages)} languages for {len(paths)} shards")
+
+        # Phase 1 : word counts of every shard, summed in a fixed (sorted) order
+        count = functools.partial(count_shard, config=self.config, trainer_config=config, jsonl_field=jsonl_field)
+        stats = TrainingStats()
+        word_counts = Counter()
+        language_counts = {} if languages is not None else None
+        ngram_counts = {} if config.collect_ngrams else None
+        num_workers = config.num_workers if num_workers is None else num_workers
+        shards = parallel_map(count, [fs_path(path) for path in paths], num_workers)
+        for index, (shard_counts, shard_stats, shard_ngrams) in enumerate(shards):
+            word_counts.update(shard_counts)
+            if languages is not None:
+                language_counts.setdefault(languages[index], Counter()).update(shard_counts)
+            for name, value in asdict(shard_stats).items():
+                if isinstance(value, int):
+                    setattr(stats, name, getattr(stats, name) + value)
+            for key, counts in (shard_ngrams or {}).items():
+                ngram_counts.setdefault(key, Counter()).update(counts)
+        word_counts = Counter(dict(sorted(word_counts.items())))
+        if ngram_counts is not None:
+            self._report_ngrams(ngram_counts, config, stats)
+
+        # Phase 2 : merges learned from the global table
+        self._train_on_word_counts(word_counts, vocab_size, allowed_special, callback, config, stats, language_counts)
+        return stats
+
+
+    def _train_on_word_counts(self, word_counts: Counter, vocab_size: int, allowed_special: set[str] | None,
+                              callback: Callable[[int, int], None] | None, config: TrainerConfig, stats: TrainingStats,
+                              language_counts: dict[str, Counter] | None = None) -> None:
+        # Build the initial vocabulary from the counted words and learn the merges
+        if config.language_budgets:
+            self._check_language_budgets(config.language_budgets, language_counts)
+
+        # Casing is carried by markers, which must be part of the vocabulary
+        if self.config.case_markers:
+            allowed_special = set(allowed_special or ()) | {CAP_TOKEN, UPPER_TOKEN}
+        if config.document_eos is not None:
+            allowed_special = set(allowed_special or ()) | {config.document_eos}
+
+        marker = self.space_marker
+
+        # Pinned special tokens take their fixed ids before anything else is allocated
+        self.reserved_id_ranges = [tuple(id_range) for id_range in config.reserved_id_ranges]
+        self.vocab = {}
+        self.inverse_vocab = {}
+        for token, token_id in config.pinned_special_tokens.items():
+            if token_id in self.vocab or self._is_reserved(token_id):
+                raise ValueError(f"Pinned id {token_id} of {token} is already taken or reserved")
+            self.vocab[token_id] = token
+            self.inverse_vocab[token] = token_id
+
+        # Initialize vocab with unique characters
+        unique_chars = [chr(i) for i in range(256)]
+        symbols = self._covered_symbols(word_counts, config.character_coverage, stats)
+        unique_chars.extend(symbol for symbol in sorted(symbols) if symbol not in unique_chars)
+        boundary = marker if self.config.word_boundary == WordBoundary.START else END_OF_WORD
+        if boundary not in unique_chars:
+            unique_chars.append(boundary)
+
+        # Add special tokens
+        unique_chars.extend(sorted(allowed_special or ()))
+
+        next_id = 0
+        for token in unique_chars:
+            if token not in self.inverse_vocab:
+                next_id = self._free_id(next_id)
+                self.vocab[next_id] = token
+                self.inverse_vocab[token] = next_id
+
+        # Tokenize the unique words, splitting them around characters left out by character_coverage
+        def fragments(word):
+            fragment = []
+            for symbol in self._symbols(word):
+                if symbol in self.inverse_vocab:
+                    fragment.append(self.inverse_vocab[symbol])
+                elif fragment:
+                    yield tuple(fragment)
+                    fragment = []
+            if fragment:
+                yield tuple(fragment)
+
+        word_freqs = Counter()
+        for word, freq in word_counts.items():
+            for fragment in fragments(word):
+                word_freqs[fragment] += freq
+
+        # Frequency of every word in each language, for the language budgets
+        word_languages = None
+        if config.language_budgets:
+            word_languages = {}
+            for language, counts in sorted(language_counts.items()):
+                for word, freq in counts.items():
+                    for fragment in fragments(word):
+                        word_languages.setdefault(fragment, Counter())[language] += freq
+
+        # Find and Replace frequent pairs
+        self.bpe_merges = {}
+        self._learn_merges(word_freqs, vocab_size, callback, config, word_languages, stats)
+
+        if config.document_eos is not None:
+            self.special_tokens_map.set("eos", config.document_eos)
+        self._invalidate_caches()
+
+
+    async def train_async(self, text: str, vocab_size: int, allowed_special: set[str] = {"<|endoftext|>"},
+                          callback: Callable[[int, int], None] | None = None, config: TrainerConfig | None = None,
+                          executor: Executor | None = None) -> TrainingStats:
+        """
+        Train the tokenizer (see train) on an executor thread, without blocking the event loop
+
+        The tokenizer must not be used for encoding until the returned coroutine completes.
+
+        Args:
+            text (str) : The text used to train the tokenizer
+            vocab_size (int) : The vocabulary size
+            allowed_special (set) : A set of included special tokens
+            callback (callable or None) : Called after each merge, from the executor thread
+            config (TrainerConfig or None) : Optional training settings
+            executor (Executor or None) : Executor to run on, the loop's default thread pool if None
+
+        Returns:
+            TrainingStats : Statistics about the training run
+        """
+
+        loop = asyncio.get_running_loop()
+        return await loop.run_in_executor(executor, functools.partial(self.train, text, vocab_size, allowed_special, callback, config))
+
+
+    @classmethod
+    def from_training(cls, text: str, vocab_size: int, config: TokenizerConfig | None = None,
+                      trainer_config: TrainerConfig | None = None, allowed_special: set[str] = {"<|endoftext|>"}) -> "BPETokenizer":
+        """
+        Construct and train a tokenizer in one step, so an untrained instance is never exposed
+
+        Args:
+            text (str) : The text used to train the tokenizer
+            vocab_size (int) : The vocabulary size
+            config (TokenizerConfig or None) : Optional tokenizer settings
+            trainer_config (TrainerConfig or None) : Optional training settings
+            allowed_special (set) : A set of included special tokens
+
+        Returns:
+            BPETokenizer : The trained tokenizer
+        """
+
+        tokenizer = cls(config)
+        tokenizer.train(text, vocab_size, allowed_special, config=trainer_config)
+        return tokenizer
+
+
+    def _covered_symbols(self, word_counts: Counter, coverage: float, stats: TrainingStats) -> set[str]:
+        # Initial symbols of the corpus, without the rarest ones beyond the character coverage
+        symbol_counts = Counter()
+        for word, freq in word_counts.items():
+            for symbol in self._symbols(word):
+                symbol_counts[symbol] += freq
+        if coverage >= 1.0:
+            return set(symbol_counts)
+
+        # The 256 base characters are always in the vocabulary and count towards the coverage
+        covered = sum(freq for symbol, freq in symbol_counts.items() if len(symbol) == 1 and ord(symbol) < 256)
+        required = coverage * sum(symbol_counts.values())
+        symbols = set()
+        for symbol, freq in sorted(symbol_counts.items(), key=lambda item: (-item[1], item[0])):
+            if len(symbol) == 1 and ord(symbol) < 256:
+                continue
+            if covered >= required:
+                stats.chars_dropped += 1
+                continue
+            symbols.add(symbol)
+            covered += freq
+        return symbols
+
+
+    def _count_training_words(self, text: str, config: TrainerConfig, stats: TrainingStats,
+                              num_workers: int | None = None, ngram_counts: dict | None = None) -> Counter:
+        """
+        Pre-tokenize a training corpus and count its unique words
+
+        Args:
+            text (str) : The training text
+            config (TrainerConfig) : The training settings
+            stats (TrainingStats) : Statistics updated with the number of filtered lines and skipped duplicates
+            num_workers (int or None) : Number of processes counting chunks, defaults to config.num_workers
+            ngram_counts (dict or None) : If given, updated with the n-gram counts of the text (see count_ngrams)
+
+        Returns:
+            Counter : Mapping of word (in vocabulary form) to its frequency
+        """
+
+        # Drop the lines rejected by the corpus filters
+        if config.filters:
+            text, stats.lines_filtered = filter_text(text, config.filters)
+
+        # Drop repeated boilerplate before counting pairs
+        if config.dedupe:
+            text, stats.duplicates_skipped = dedupe_text(text, config.dedupe_unit)
+
+        # N-gram statistics describe the corpus as given, before any tokenizer-specific transformation
+        if ngram_counts is not None:
+            count_ngrams(text, config.ngram_orders, ngram_counts)
+
+        # Casing is carried by markers, so merges are learned on lowercased text
+        if self.config.case_markers:
+            text = text.lower()
+
+        # Byte-level merges are learned on the UTF-8 bytes of the text
+        if self.config.byte_level:
+            text = byte_text(text)
+
+        # Words never span two documents
+        documents = [text] if config.document_separator is None else re.split(config.document_separator, text)
+
+        # Words are the pre-tokens encode produces, so merges never span words, lines or special tokens
+        chunks = []
+        for document in documents:
+            # Count the pre-tokens, in parallel chunks split after newline runs
+            start = 0
+            while start < len(document):
+                end = document.find("\n", start + PARALLEL_CHUNK_CHARS)
+                while end != -1 and end < len(document) and document[end] == "\n":
+                    end += 1
+                end = len(document) if end == -1 else end
+                # A continuation chunk starts a new line of the document, whose first word carries the space marker
+                chunks.append(document[start:end] if start == 0 else "\n" + document[start:end])
+                start = end
+
+        # Small documents are grouped so every worker receives about PARALLEL_CHUNK_CHARS characters
+        groups = [[]]
+        group_size = 0
+        for chunk in chunks:
+            if group_size >= PARALLEL_CHUNK_CHARS:
+                groups.append([])
+                group_size = 0
+            groups[-1].append(chunk)
+            group_size += len(chunk)
+
+        word_counts = Counter()
+        num_workers = config.num_workers if num_workers is None else num_workers
+        if chunks:
+            count_chunk = functools.partial(count_pre_tokens, config=self.config)
+            for counts in parallel_map(functools.partial(count_documents, count_chunk=count_chunk), groups, num_workers):
+                word_counts.update(counts)
+
+        return word_counts
+
+
+    @staticmethod
+    def _check_language_budgets(budgets: dict[str, tuple[float, float]], language_counts: dict[str, Counter] | None) -> None:
+        if language_counts is None:
+            raise ValueError("language_budgets needs shards tagged with a language : use train_from_shards with languages")
+        for language, (minimum, maximum) in budgets.items():
+            if language not in language_counts:
+                raise ValueError(f"No shard is tagged with the budgeted language {language!r}")
+            if not 0.0 <= minimum <= maximum <= 1.0:
+                raise ValueError(f"The budget of {language!r} must satisfy 0 <= minimum <= maximum <= 1, got {minimum}, {maximum}")
+        if sum(minimum for minimum, _ in budgets.values()) > 1.0:
+            raise ValueError("The minimum shares of language_budgets add up to more than 1")
+
+
+    def _report_ngrams(self, ngram_counts: dict[tuple[str, int], Counter], config: TrainerConfig, stats: TrainingStats) -> None:
+        # Keep the most frequent n-grams (ties by n-gram, for reproducible output) and write the sidecar file
+        top = {key : heapq.nsmallest(config.ngram_top_k, counts.items(), key=lambda item: (-item[1], item[0]))
+               for key, counts in sorted(ngram_counts.items())}
+        stats.ngrams = NgramStats(
+            char_ngrams={n : ngrams for (kind, n), ngrams in top.items() if kind == "char"},
+            word_ngrams={n : ngrams for (kind, n), ngrams in top.items() if kind == "word"},
+        )
+        if config.ngram_path is not None:
+            sidecar = {kind : {str(n) : [list(item) for item in ngrams] for n, ngrams in table.items()}
+                       for kind, table in (("char", stats.ngrams.char_ngrams), ("word", stats.ngrams.word_ngrams))}
+            with open_text(config.ngram_path, "w", atomic=True) as file:
+                json.dump(sidecar, file, ensure_ascii=False, indent=4)
+
+
+    def _learn_merges(self, word_freqs: dict[tuple[int, ...], int], vocab_size: int,
+                      callback: Callable[[int, int], None] | None = None, config: TrainerConfig | None = None,
+                      word_languages: dict[tuple[int, ...], Counter] | None = None, stats: TrainingStats | None = None) -> None:
+        """
+        Learn merges from a word frequency table until the vocabulary reaches vocab_size
+
+        Pair counts are weighted by word frequency and only updated for the words
+        containing the merged pair, so each merge costs time proportional to the
+        affected words rather than to the whole corpus.
+
+        Args:
+            word_freqs (dict) : Mapping of word (as token ids) to its corpus frequency
+            vocab_size (int) : The vocabulary size
+            callback (callable or None) : Called after each merge with the new token id and the corpus length in tokens
+            config (TrainerConfig or None) : Optional training settings restricting which merges are allowed
+            word_languages (dict or None) : Frequency of each word in each language, to enforce config.language_budgets
+            stats (TrainingStats or None) : Statistics updated with the number of merges of each language
+        """
+
+        config = config or TrainerConfig()
+        never_merge = [re.compile(pattern) for pattern in config.never_merge]
+        if config.max_token_length_unit not in ("chars", "bytes"):
+            raise ValueError("Invalid max_token_length_unit. Choose 'chars' or 'bytes'")
+        measure = len if config.max_token_length_unit == "chars" else lambda token: len(token.encode("utf-8", "surrogatepass"))
+
+        words = [list(word) for word in word_freqs]
+        freqs = list(word_freqs.values())
+        self._training_state = {"words" : words, "freqs" : freqs, "vocab_size" : vocab_size, "config" : config}
+        num_tokens = sum(len(word) * freq for word, freq in zip(words, freqs))
+
+        # Weighted pair counts and the words each pair occurs in
+        pair_counts = Counter()
+        pair_words = {}
+        for index, (word, freq) in enumerate(zip(words, freqs)):
+            for pair in zip(word, word[1:]):
+                pair_counts[pair] += freq
+                pair_words.setdefault(pair, set()).add(index)
+
+        # Pair counts of each language, when the merges are budgeted per language
+        budgets = config.language_budgets if word_languages is not None else {}
+        languages = [word_languages.get(word, {}) for word in word_freqs] if budgets else []
+        language_pair_counts = {language : Counter() for language in sorted({name for counts in languages for name in counts})}
+        for word, counts in zip(words, languages):
+            for pair in zip(word, word[1:]):
+                for language, freq in counts.items():
+                    language_pair_counts[language][pair] += freq
+
+        # Merged tokens take the lowest ids not used by the vocabulary or reserved
+        next_id = 0
+
+        def apply_merge(pair_id, merged_token):
+            nonlocal num_tokens, next_id
+            if merged_token in self.inverse_vocab:
+                new_id = self.inverse_vocab[merged_token]
+            else:
+                next_id = new_id = self._free_id(next_id)
+            for index in sorted(pair_words.pop(pair_id, ())):
+                word, freq = words[index], freqs[index]
+                for pair in zip(word, word[1:]):
+                    pair_counts[pair] -= freq
+                merged = self.replace_pair(word, pair_id, new_id)
+                num_tokens -= (len(word) - len(merged)) * freq
+                for pair in zip(merged, merged[1:]):
+                    pair_counts[pair] += freq
+                    pair_words.setdefault(pair, set()).add(index)
+                for language, language_freq in (languages[index].items() if budgets else ()):
+                    counts = language_pair_counts[language]
+                    for pair in zip(word, word[1:]):
+                        counts[pair] -= language_freq
+                    for pair in zip(merged, merged[1:]):
+                        counts[pair] += language_freq
+                words[index] = merged
+            pair_counts.pop(pair_id, None)
+            for counts in language_pair_counts.values():
+                counts.pop(pair_id, None)
+
+            self.bpe_merges[pair_id] = new_id
+            self.vocab[new_id] = merged_token
+            self.inverse_vocab[merged_token] = new_id
+            if callback is not None:
+                callback(new_id, num_tokens)
+
+        # Protected tokens are built first by a chain of forced merges
+        for token in config.protected_tokens:
+            symbols = self._symbols(token)
+            for symbol in symbols:
+                if symbol not in self.inverse_vocab:
+                    next_id = self._free_id(next_id)
+                    self.vocab[next_id] = symbol
+                    self.inverse_vocab[symbol] = next_id
+            current = symbols[0]
+            for symbol in symbols[1:]:
+                pair_id = (self.inverse_vocab[current], self.inverse_vocab[symbol])
+                current += symbol
+                if pair_id not in self.bpe_merges:
+                    apply_merge(pair_id, current)
+
+        # Merge counts each language must reach and may not exceed, as shares of the merges still to learn
+        num_merges = max(vocab_size - len(self.vocab), 0)
+        limits = {language : (math.ceil(budgets.get(language, (0.0, 1.0))[0] * num_merges),
+                              math.floor(budgets.get(language, (0.0, 1.0))[1] * num_merges)) for language in language_pair_counts}
+        language_merges = Counter()
+
+        def language_of(pair):
+            # The language contributing most of the pair count, ties by language name
+            return max(language_pair_counts, key=lambda language: language_pair_counts[language][pair])
+
+        def allowed_languages():
+            # Languages below their minimum get every remaining merge once the minimums need them all
+            open_languages = {language for language, (_, maximum) in limits.items() if language_merges[language] < maximum}
+            missing = {language : minimum - language_merges[language] for language, (minimum, _) in limits.items()
+                       if language_merges[language] < minimum}
+            if missing and sum(missing.values()) >= num_merges - sum(language_merges.values()):
+                return [set(missing) & open_languages, open_languages]
+            return [open_languages]
+
+        blocked = set()
+        while len(self.vocab) < vocab_size:
+            # Most frequent pair, ties broken by the smallest pair for reproducibility
+            candidates = ((pair, count) for pair, count in pair_counts.items() if pair not in blocked and count > 0)
+            if budgets:
+                candidates = list(candidates)
+            best = None
+            # A minimum that no pair can serve any more is given up rather than stopping training
+            for allowed in (allowed_languages() if budgets else [None]):
+                eligible = candidates if allowed is None else [item for item in candidates if language_of(item[0]) in allowed]
+                best = max(eligible, key=lambda item: (item[1], -item[0][0], -item[0][1]), default=None)
+                if best is not None:
+                    break
+            if best is None:
+                break
+            pair_id = best[0]
+
+            merged_token = self.vocab[pair_id[0]] + self.vocab[pair_id[1]]
+            too_long = config.max_token_length is not None and measure(merged_token) > config.max_token_length
+            if too_long or any(pattern.search(merged_token) for pattern in never_merge):
+                blocked.add(pair_id)
+                continue
+            if budgets:
+                language_merges[language_of(pair_id)] += 1
+            apply_merge(pair_id, merged_token)
+
+            # Periodically persist progress so an interrupted run can be resumed
+            if config.checkpoint_path is not None and config.checkpoint_every > 0 and len(self.bpe_merges) % config.checkpoint_every == 0:
+                self.save_checkpoint(config.checkpoint_path)
+
+        # Tokens fully absorbed by longer merges keep a frequency of 0
+        frequencies = dict.fromkeys(self.vocab, 0)
+        for word, freq in zip(words, freqs):
+            for token_id in word:
+                frequencies[token_id] += freq
+        self.token_frequencies = frequencies
+        if budgets and stats is not None:
+            stats.merges_per_language = {language : language_merges[language] for language in language_pair_counts}
+
+
+    def save_checkpoint(self, path: PathOrFile, create_dirs: bool = False) -> None:
+        """
+        Save the state of the latest training run so it can be continued with resume_training
+
+        The checkpoint holds the vocabulary, the merges learned so far, the target
+        vocabulary size, the training settings and the word frequency table with
+        every merge applied. It can be written from a training callback.
+
+        Args:
+            path (str, bytes, PathLike or file-like) : Path or file object to write to
+            create_dirs (bool) : Create the missing parent directories of the path
+        """
+
+        if self._training_state is None:
+            raise ValueError("No training run to checkpoint : call train first")
+
+        state = self._training_state
+        checkpoint = {
+            "tokenizer" : self._state(),
+            "vocab_size" : state["vocab_size"],
+            "trainer_config" : asdict(state["config"]),
+            "words" : state["words"],
+            "freqs" : state["freqs"],
+        }
+        with open_text(path, "w", atomic=True, create_dirs=create_dirs) as file:
+            json.dump(checkpoint, file, ensure_ascii=False)
+
+
+    def resume_training(self, path: PathOrFile, corpus: str | None = None, vocab_size: int | None = None,
+                        callback: Callable[[int, int], None] | None = None, config: TrainerConfig | None = None) -> TrainingStats:
+        """
+        Continue a training run from a checkpoint written by save_checkpoint
+
+        The TokenizerConfig of the interrupted run is restored from the checkpoint.
+
+        Args:
+            path (str, bytes, PathLike or file-like) : Path or file object to read the checkpoint from
+            corpus (str or None) : The training text, only needed to rebuild a checkpoint saved without its word table
+            vocab_size (int or None) : The vocabulary size, defaults to the target of the interrupted run
+            callback (callable or None) : Called after each merge with the new token id and the corpus length in tokens
+            config (TrainerConfig or None) : Training settings, defaults to those of the interrupted run
+
+        Returns:
+            TrainingStats : Statistics about the resumed part of the run
+        """
+
+        self._check_not_frozen("resume training")
+        with open_text(path, "r") as file:
+            checkpoint = json.load(file)
+
+        self._load_state(checkpoint["tokenizer"])
+        config = config or TrainerConfig(**checkpoint["trainer_config"])
+        vocab_size = vocab_size or checkpoint["vocab_size"]
+        stats = TrainingStats()
+
+        word_freqs = Counter()
+        if checkpoint.get("words") is not None:
+            for word, freq in zip(checkpoint["words"], checkpoint["freqs"]):
+                word_freqs[tuple(word)] += freq
+        elif corpus is not None:
+            # Rebuild the word table by replaying the learned merges on the corpus words
+            ranks = {pair_id : rank for rank, pair_id in enumerate(self.bpe_merges)}
+            for word, freq in self._count_training_words(corpus, config, stats).items():
+                symbols = self._symbols(word)
+                missing = [symbol for symbol in symbols if symbol not in self.inverse_vocab]
+                if missing:
+                    raise ValueError(f"Corpus characters missing from the checkpoint vocabulary : {sorted(set(missing))}")
+                word_freqs[tuple(self._apply_merges([self.inverse_vocab[symbol] for symbol in symbols], ranks))] += freq
+        else:
+            raise ValueError("The checkpoint has no word table : pass the training corpus to rebuild it")
+
+        self._learn_merges(word_freqs, vocab_size, callback, config)
+
+        self._invalidate_caches()
+        return stats
+
+
+    def _apply_merges(self, token_ids: list[int], ranks: dict[tuple[int, int], int]) -> list[int]:
+        # Apply the learned merges in the order they were learned
+        while len(token_ids) > 1:
+            pair_id = min(zip(token_ids, token_ids[1:]), key=lambda pair: ranks.get(pair, float("inf")))
+            if pair_id not in ranks:
+                break
+            token_ids = self.replace_pair(token_ids, pair_id, self.bpe_merges[pair_id])
+        return token_ids
+
+
+    def train_from_files(self, paths: list[FilePath], vocab_size: int, allowed_special: set[str] = {"<|endoftext|>"},
+                         config: TrainerConfig | None = None, jsonl_field: str | None = None) -> TrainingStats:
+        """
+        Train BPE Tokenizer on the concatenation of several text files
+
+        Args:
+            paths (list[str, bytes or PathLike]) : Paths of the UTF-8 text files used for training, decompressed
+                on the fly when they end in .gz, .xz or .zst
+            vocab_size (int) : The vocabulary size
+            allowed_special (set) : A set of included special tokens
+            config (TrainerConfig or None) : Optional training settings
+            jsonl_field (str or None) : Read the files as JSON Lines and train on this field of every record
+                (dotted for nested objects, e.g. "meta.text"), skipping malformed records. None reads plain text
+
+        Returns:
+            TrainingStats : Statistics about the training run
+        """
+
+        texts = []
+        skipped = []
+        for path in paths:
+            with open_text(path, "r") as file:
+                if jsonl_field is None:
+                    texts.append(file.read())
+                else:
+                    texts.extend(iter_jsonl_field(file, jsonl_field, skipped))
+
+        stats = self.train("\n".join(texts), vocab_size, allowed_special, config=config)
+        stats.records_skipped = len(skipped)
+        return stats
+
+
+    @classmethod
+    def from_vocab_and_merges(cls, vocab: dict[str, int], merges: list[tuple[str, str]],
+                              config: TokenizerConfig | None = None) -> "BPETokenizer":
+        """
+        Construct a tokenizer from an in-memory vocabulary and merge list
+
+        Args:
+            vocab (dict[str, int]) : Mapping of token string to token id
+            merges (list[tuple[str, str]]) : Merged token string pairs, in merge order
+            config (TokenizerConfig or None) : Optional tokenizer settings
+
+        Returns:
+            BPETokenizer : The constructed tokenizer
+        """
+
+        tokenizer = cls(config)
+        tokenizer.vocab = {int(i) : token for token, i in vocab.items()}
+        tokenizer.inverse_vocab = {token : int(i) for token, i in vocab.items()}
+
+        for left, right in merges:
+            for part in (left, right, left + right):
+                if part not in tokenizer.inverse_vocab:
+                    raise LucidInvalidMerge(left, right, part)
+            pair = (tokenizer.inverse_vocab[left], tokenizer.inverse_vocab[right])
+            tokenizer.bpe_merges[pair] = tokenizer.inverse_vocab[left + right]
+
+        tokenizer._invalidate_caches()
+        return tokenizer
+
+
+    @classmethod
+    def load_subword_nmt(cls, codes_path: PathOrFile, vocab_path: PathOrFile | None = None,
+                         config: TokenizerConfig | None = None) -> "BPETokenizer":
+        """
+        Import the BPE codes (and optionally the vocabulary) of a subword-nmt or fairseq model
+
+        The codes file holds one merge "left right" per line, optionally preceded by a
+        "#version" header, with "</w>" ending word-final symbols. Vocabulary entries use
+        the "@@" suffix for word-internal pieces and are converted to the "</w>" form.
+
+        Args:
+            codes_path (str, PathLike or file-like) : The BPE codes file
+            vocab_path (str, PathLike or file-like or None) : Optional "token count" vocabulary file
+            config (TokenizerConfig or None) : Tokenizer settings, defaults to end-of-word marking
+
+        Returns:
+            BPETokenizer : The imported tokenizer
+        """
+
+        config = config or TokenizerConfig(word_boundary=WordBoundary.END)
+        if config.word_boundary != WordBoundary.END:
+            raise ValueError("subword-nmt models require TokenizerConfig(word_boundary=WordBoundary.END)")
+
+        merges = []
+        with open_text(codes_path, "r") as file:
+            for line_number, line in enumerate(file, 1):
+                line = line.rstrip("\r\n")
+                if not line or (line_number == 1 and line.startswith("#version")):
+                    continue
+                parts = line.split(" ")
+                if len(parts) != 2 or not all(parts):
+                    raise ValueError(f"Invalid BPE code on line {line_number} : {line!r}")
+                merges.append((parts[0], parts[1]))
+
+        # Base characters first, then every symbol in the order merges create them
+        tokens = [chr(i) for i in range(256)] + [END_OF_WORD]
+        for left, right in merges:
+            tokens.extend((left, right, left + right))
+
+        if vocab_path is not None:
+            with open_text(vocab_path, "r") as file:
+                for line in file:
+                    token = line.rstrip("\r\n").rsplit(" ", 1)[0]
+                    if token:
+                        tokens.append(token[:-2] if token.endswith("@@") else token + END_OF_WORD)
+
+        vocab = {token : i for i, token in enumerate(dict.fromkeys(tokens))}
+        return cls.from_vocab_and_merges(vocab, merges, config)
+
+
+    def token_frequency(self, token_id: int) -> int | None:
+        """
+        Number of occurrences of a token in the training corpus, segmented with every learned merge
+
+        Tokens that only exist as parts of longer merged tokens have a frequency of 0.
+
+        Args:
+            token_id (int) : The token id
+
+        Returns:
+            int or None : The frequency, None if no frequencies were recorded (e.g. tokenizers saved
+            by an earlier version) or the token was added after training
+        """
+
+        if token_id not in self.vocab:
+            raise LucidTokenIdNotFound(token_id)
+        return self.token_frequencies.get(token_id)
+
+
+    def get_vocab(self) -> dict[str, int]:
+        """
+        Return a copy of the vocabulary
+
+        Returns:
+            dict[str, int] : Mapping of token string to token id
+        """
+
+        return dict(self.inverse_vocab)
+
+
+    def get_merges(self) -> list[tuple[str, str]]:
+        """
+        Return the BPE merges as token string pairs
+
+        Returns:
+            list[tuple[str, str]] : Merged token string pairs, in merge order
+        """
+
+        return [(self.vocab[p0], self.vocab[p1]) for p0, p1 in self.bpe_merges]
+
+
+    @staticmethod
+    def suggest_vocab_size(corpus: str, target_compression: float, max_vocab_size: int,
+                           checkpoint_every: int = 1000, allowed_special: set[str] = {"<|endoftext|>"}) -> VocabSizeSuggestion:
+        """
+        Train incrementally and measure compression to find the smallest adequate vocabulary
+
+        Args:
+            corpus (str) : The text used to train the tokenizer
+            target_compression (float) : The desired tokens-per-character on the corpus
+            max_vocab_size (int) : The largest vocabulary size to try
+            checkpoint_every (int) : Number of merges between compression measurements
+            allowed_special (set) : A set of included special tokens
+
+        Returns:
+            VocabSizeSuggestion : The compression curve and the suggested vocabulary size
+        """
+
+        suggestion = VocabSizeSuggestion()
+        num_chars = max(len(corpus), 1)
+        merges_done = 0
+        last_state = None
+
+        def record(new_id, num_tokens):
+            nonlocal merges_done, last_state
+            merges_done += 1
+            last_state = (new_id + 1, num_tokens / num_chars)
+            if merges_done % checkpoint_every == 0:
+                suggestion.curve.append(last_state)
+
+        BPETokenizer().train(corpus, max_vocab_size, allowed_special, callback=record)
+        if last_state is not None and (not suggestion.curve or suggestion.curve[-1] != last_state):
+            suggestion.curve.append(last_state)
+
+        for vocab_size, tokens_per_char in suggestion.curve:
+            if tokens_per_char <= target_compression:
+                suggestion.suggested_vocab_size = vocab_size
+                break
+
+        return suggestion
+
+
+    def encode(self, text: str, allowed_special: set[str] | str | None = None,
+               disallowed_special: set[str] | str | None = None) -> list[int]:
+        """
+        Encode the input text into a list of token IDs
+
+        As in tiktoken, "all" allows (or disallows) every special token of the vocabulary and
+        "none" none of them. When disallowed_special is None, special-looking tokens ("<|...|>")
+        only raise if some special tokens are allowed.
+
+        Args:
+            text (str) : The input text to encode
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the text,
+                "all" meaning every special token not allowed
+
+        Returns:
+            List of token IDs.
+        """
+
+        return [token_id for token_id, _, _ in self._encode_spans(text, allowed_special, disallowed_special=disallowed_special)]
+
+
+    async def encode_async(self, text: str, allowed_special: set[str] | str | None = None,
+                           disallowed_special: set[str] | str | None = None, executor: Executor | None = None) -> list[int]:
+        """
+        Encode the input text (see encode) on an executor thread, without blocking the event loop
+
+        Args:
+            text (str) : The input text to encode
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the text
+            executor (Executor or None) : Executor to run on, the loop's default thread pool if None
+
+        Returns:
+            List of token IDs.
+        """
+
+        loop = asyncio.get_running_loop()
+        return await loop.run_in_executor(executor, functools.partial(self.encode, text, allowed_special, disallowed_special))
+
+
+    def encode_untrusted(self, text: str, policy: SpecialTokenPolicy | str | None = None) -> list[int]:
+        """
+        Encode user-supplied text so it can never produce special token ids (prompt-injection hardening)
+
+        Args:
+            text (str) : The untrusted text
+            policy (SpecialTokenPolicy, str or None) : What to do with special token strings found in the text.
+                ESCAPE needs U+200B to be encodable (byte-level mode, in the vocabulary or an unk token).
+                None picks ESCAPE when U+200B is a byte or a token of the vocabulary, RAISE otherwise
+
+        Returns:
+            List of token IDs, none of them a special token unless the text contains characters
+            encoded with the unk token
+        """
+
+        if policy is None:
+            escapable = self.config.byte_level or ESCAPE_CHAR in self.inverse_vocab
+            policy = SpecialTokenPolicy.ESCAPE if escapable else SpecialTokenPolicy.RAISE
+        policy = SpecialTokenPolicy(policy)
+        if policy == SpecialTokenPolicy.RAISE:
+            return self.encode(text, "none", "all")
+        if policy == SpecialTokenPolicy.ESCAPE:
+            if not (self.config.byte_level or ESCAPE_CHAR in self.inverse_vocab or self.get_special_token_id("unk") is not None):
+                raise LucidInvalidConfig("SpecialTokenPolicy.ESCAPE needs U+200B to be encodable : use a byte-level tokenizer, "
+                                         "one with U+200B in its vocabulary or an unk token, or another policy")
+            text = self.escape_special_tokens(text)
+        return self.encode(text, "none", "none")
+
+
+    def escape_special_tokens(self, text: str) -> str:
+        """
+        Neutralize the special token strings of a text, reversibly
+
+        An invisible zero width space (U+200B) is inserted after the first character of every
+        special token string, so the text no longer contains any of them. Zero width spaces already
+        present are doubled, which makes unescape_special_tokens an exact inverse.
+
+        Args:
+            text (str) : The text to escape
+
+        Returns:
+            str : The escaped text
+        """
+
+        text = text.replace(ESCAPE_CHAR, ESCAPE_CHAR * 2)
+        special_tokens = self.special_tokens()
+        if not special_tokens:
+            return text
+        key = frozenset(special_tokens)
+        if key not in self._special_matchers:
+            self._special_matchers[key] = AhoCorasick(sorted(key))
+
+        pieces = []
+        last_index = 0
+        for start, end, _ in self._special_matchers[key].find_leftmost_longest(text):
+            pieces.append(text[last_index:start + 1])
+            pieces.append(ESCAPE_CHAR)
+            last_index = start + 1
+        pieces.append(text[last_index:])
+        return "".join(pieces)
+
+
+    @staticmethod
+    def unescape_special_tokens(text: str) -> str:
+        """
+        Undo escape_special_tokens
+
+        Args:
+            text (str) : The escaped text
+
+        Returns:
+            str : The original text
+        """
+
+        return UNESCAPE_PATTERN.sub(r"\1", text)
+
+
+    def encode_continuation(self, text: str, allowed_special: set[str] | str | None = None,
+                            disallowed_special: set[str] | str | None = None) -> list[int]:
+        """
+        Encode text that continues a previously encoded text on the same line
+
+        encode treats the start of its input as the start of a document : a leading space is
+        dropped (or, with the code pre-tokenizer, taken as indentation). Here a first word preceded
+        by whitespace gets the space marker instead, as it would inside the joined text.
+
+        Args:
+            text (str) : The continuation text
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the text
+
+        Returns:
+            List of token IDs.
+        """
+
+        spans = self._encode_spans(text, allowed_special, disallowed_special=disallowed_special, continuation=True)
+        return [token_id for token_id, _, _ in spans]
+
+
+    def encode_pair(self, a: str, b: str, allowed_special: set[str] | str | None = None,
+                    disallowed_special: set[str] | str | None = None) -> list[int]:
+        """
+        Encode two texts meant to be concatenated, such that decode(encode_pair(a, b)) == a + b
+
+        The ids of a come first and do not depend on b, so a shared prompt prefix keeps the
+        same ids whatever follows it. Trailing spaces of a are encoded with b, where they
+        become the space marker of its first word. The guarantee holds for texts that
+        round-trip through encode and decode on their own.
+
+        Args:
+            a (str) : The first text
+            b (str) : The text following it
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the texts
+
+        Returns:
+            List of token IDs.
+        """
+
+        trailing = re.search(r"[^\S\n]*$", a).group(0)
+        a = a[:len(a) - len(trailing)]
+        return self.encode(a, allowed_special, disallowed_special) + self.encode_continuation(trailing + b, allowed_special, disallowed_special)
+
+
+    def encode_bytes(self, data: bytes, allowed_special: set[str] | str | None = None,
+                     disallowed_special: set[str] | str | None = None) -> list[int]:
+        """
+        Encode raw bytes without decoding them as UTF-8 (requires byte-level mode)
+
+        Args:
+            data (bytes) : The input bytes, which need not be valid UTF-8
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the data
+
+        Returns:
+            list[int] : The list of token IDs
+        """
+
+        if not self.config.byte_level:
+            raise ValueError("encode_bytes requires a tokenizer with TokenizerConfig(byte_level=True)")
+        spans = self._encode_spans(bytes(data).decode("latin-1"), allowed_special, is_byte_text=True,
+                                   disallowed_special=disallowed_special)
+        return [token_id for token_id, _, _ in spans]
+
+
+    def encode_lossy(self, data: bytes | str, errors: InvalidUtf8Policy | str | None = None,
+                     allowed_special: set[str] | str | None = None) -> list[int]:
+        """
+        Encode text that may not be valid UTF-8 (scraped pages, log files) instead of failing on it
+
+        Strings may hold lone surrogates (e.g. from surrogateescape decoding), which are treated like
+        the invalid bytes they encode to. Outside byte-level mode, U+FFFD is encoded like any other
+        character : as the unk token when the vocabulary lacks it, raising LucidCharacterNotFound without one.
+
+        Args:
+            data (bytes or str) : The input bytes, or a string possibly holding lone surrogates
+            errors (InvalidUtf8Policy, str or None) : "replace" or "byte_fallback", defaults to
+                "byte_fallback" in byte-level mode and "replace" otherwise
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+
+        Returns:
+            list[int] : The list of token IDs
+        """
+
+        if isinstance(data, str):
+            data = data.encode("utf-8", "surrogatepass")
+        if errors is None:
+            errors = InvalidUtf8Policy.BYTE_FALLBACK if self.config.byte_level else InvalidUtf8Policy.REPLACE
+        errors = InvalidUtf8Policy(errors)
+
+        if errors == InvalidUtf8Policy.BYTE_FALLBACK:
+            if not self.config.byte_level:
+                raise ValueError("The byte_fallback policy requires a tokenizer with TokenizerConfig(byte_level=True)")
+            return self.encode_bytes(data, allowed_special)
+        return self.encode(bytes(data).decode("utf-8", "replace"), allowed_special)
+
+
+    def encode_sentences(self, text: str, allowed_special: set[str] | None = None, add_eos: bool = False,
+                         abbreviations: set[str] | None = None) -> list[list[int]]:
+        """
+        Split text into sentences and encode each one separately
+
+        Args:
+            text (str) : The input text
+            allowed_special (set or None) : Special tokens to allow passthrough
+            add_eos (bool) : Append the eos special token after every sentence
+            abbreviations (set[str] or None) : Abbreviations passed to split_sentences
+
+        Returns:
+            list[list[int]] : The token IDs of each sentence
+        """
+
+        eos_id = self.require_special_token_id("eos") if add_eos else None
+        encoded = []
+        for sentence, _ in split_sentences(text, abbreviations):
+            token_ids = self.encode(sentence, allowed_special)
+            if eos_id is not None:
+                token_ids.append(eos_id)
+            encoded.append(token_ids)
+        return encoded
+
+
+    def encode_plus(self, text: str, allowed_special: set[str] | str | None = None,
+                    disallowed_special: set[str] | str | None = None) -> Encoding:
+        """
+        Encode the input text into an Encoding carrying character offsets
+
+        Args:
+            text (str) : The input text to encode
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the text
+
+        Returns:
+            Encoding : Token IDs with the (start, end) character span and source word of each token
+        """
+
+        spans = self._encode_spans(text, allowed_special, disallowed_special=disallowed_special)
+        return Encoding(
+            ids=[token_id for token_id, _, _ in spans],
+            attention_mask=[1] * len(spans),
+            offsets=[offsets for _, offsets, _ in spans],
+            word_ids=[word_id for _, _, word_id in spans],
+        )
+
+
+    def encode_segments(self, segments: list[Segment], allowed_special: set[str] | str | None = None,
+                        disallowed_special: set[str] | str | None = None) -> Encoding:
+        """
+        Encode several segments, each between its own control tokens, into one Encoding
+
+        Every segment is encoded on its own, so no merge crosses a segment boundary, and no text
+        is concatenated. Offsets are relative to the text of each token's segment, control tokens
+        getting an empty span at its start (prefix) or end (suffix).
+
+        Args:
+            segments (list[Segment]) : The segments, in order
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough in the segment texts
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the segment texts
+
+        Returns:
+            Encoding : Token IDs with offsets, word ids (unique across segments) and the segment index of each token
+                as token_type_ids
+        """
+
+        encoding = Encoding(ids=[], attention_mask=[], token_type_ids=[], offsets=[], word_ids=[])
+        num_words = 0
+        for index, segment in enumerate(segments):
+            control_ids = [[self._control_token_id(token) for token in tokens]
+                           for tokens in (segment.prefix_tokens, segment.suffix_tokens)]
+            spans = [(token_id, (0, 0), None) for token_id in control_ids[0]]
+            for token_id, offsets, word_id in self._encode_spans(segment.text, allowed_special,
+                                                                 disallowed_special=disallowed_special):
+                spans.append((token_id, offsets, None if word_id is None else num_words + word_id))
+            end = len(segment.text)
+            spans.extend((token_id, (end, end), None) for token_id in control_ids[1])
+
+            num_words = max((word_id + 1 for _, _, word_id in spans if word_id is not None), default=num_words)
+            encoding.ids.extend(token_id for token_id, _, _ in spans)
+            encoding.offsets.extend(offsets for _, offsets, _ in spans)
+            encoding.word_ids.extend(word_id for _, _, word_id in spans)
+            encoding.token_type_ids.extend([index] * len(spans))
+        encoding.attention_mask = [1] * len(encoding.ids)
+        return encoding
+
+
+    def _control_token_id(self, token: str | int) -> int:
+        if isinstance(token, int):
+            if token not in self.vocab:
+                raise LucidTokenIdNotFound(token)
+            return token
+        if token not in self.inverse_vocab:
+            raise LucidSpecialTokenNotFound(token)
+        return self.inverse_vocab[token]
+
+
+    def encode_with_offsets(self, text: str, allowed_special: set[str] | str | None = None,
+                            disallowed_special: set[str] | str | None = None) -> list[tuple[int, tuple[int, int]]]:
+        """
+        Encode the input text, pairing each token ID with its character span in the text
+
+        Args:
+            text (str) : The input text to encode
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in the text
+
+        Returns:
+            list[tuple[int, tuple[int, int]]] : (token_id, (start, end)) for every token
+        """
+
+        spans = self._encode_spans(text, allowed_special, disallowed_special=disallowed_special)
+        return [(token_id, offsets) for token_id, offsets, _ in spans]
+
+
+    def special_tokens(self) -> set[str]:
+        """
+        Collect the special tokens of the vocabulary
+
+        Returns:
+            set[str] : Tokens of the form "<|...|>" plus the tokens assigned to a role, language or token group
+        """
+
+        special = {token for token in self.inverse_vocab if token.startswith("<|") and token.endswith("|>")}
+        registered = [*self.special_tokens_map.to_dict().values(), *self.language_tags.values(),
+                      *(token for tokens in self.token_groups.values() for token in tokens)]
+        special.update(token for token in registered if token in self.inverse_vocab)
+        return special
+
+
+    def _resolve_special(self, value: set[str] | str | None, name: str) -> set[str] | None:
+        # Expand the "all" / "none" shorthands and the token group names into a set of tokens
+        if value == "all":
+            return self.special_tokens()
+        if value == "none":
+            return set()
+        if isinstance(value, str):
+            if value in self.token_groups:
+                return set(self.token_groups[value])
+            raise ValueError(f"{name} must be a set of tokens or groups, a token group, 'all', 'none' or None, got {value!r}")
+        return None if value is None else {token for entry in value for token in self.token_groups.get(entry, (entry,))}
+
+
+    def _encode_spans(self, text: str, allowed_special: set[str] | str | None, is_byte_text: bool = False,
+                      disallowed_special: set[str] | str | None = None,
+                      continuation: bool = False) -> list[tuple[int, tuple[int, int], int | None]]:
+        if self.metrics is None:
+            return self._collect_spans(text, allowed_special, is_byte_text, disallowed_special, continuation)
+        try:
+            spans = self._collect_spans(text, allowed_special, is_byte_text, disallowed_special, continuation)
+        except LucidError as error:
+            self.metrics.increment(ENCODE_ERRORS, labels={"kind" : error.code})
+            raise
+        self.metrics.increment(TEXTS_ENCODED)
+        self.metrics.increment(TOKENS_PRODUCED, len(spans))
+        return spans
+
+
+    def _collect_spans(self, text: str, allowed_special: set[str] | str | None, is_byte_text: bool,
+                       disallowed_special: set[str] | str | None, continuation: bool) -> list[tuple[int, tuple[int, int], int | None]]:
+        self._require_trained("encode")
+        limits = self.encode_limits
+        if limits is not None and limits.max_input_bytes is not None:
+            self._check_input_size(text, limits.max_input_bytes, is_byte_text)
+        max_tokens = limits.max_output_tokens if limits is not None else None
+        # Raw bytes only count ASCII whitespace, as other latin-1 "spaces" are parts of UTF-8 characters
+        blank = (self.config.blank_input != BlankInputPolicy.ENCODE
+                 and not (text.strip(" \t\n\r\f\v") if is_byte_text else text.strip()))
+        # In byte-level mode the text is encoded as its UTF-8 bytes, so offsets are byte offsets
+        if self.config.byte_level and not is_byte_text:
+            text = byte_text(text)
+        if blank:
+            return self._encode_blank(text)
+        allowed_special = self._resolve_special(allowed_special, "allowed_special") or set()
+        disallowed_special = self._resolve_special(disallowed_special, "disallowed_special")
+        if disallowed_special is None:
+            # Special-looking tokens are only rejected once some special tokens are allowed
+            disallowed_special = ({token for token in self.inverse_vocab if token.startswith("<|") and token.endswith("|>")}
+                                  if allowed_special else set())
+        disallowed_special -= allowed_special
+        spans = []
+        last_index = 0
+        # Number of words encoded so far, used to keep word ids unique across special token splits
+        num_words = 0
+        if allowed_special:
+            # Leftmost-longest matching, so a special token never loses to another one it starts with
+            key = frozenset(allowed_special)
+            if key not in self._special_matchers:
+                self._special_matchers[key] = AhoCorasick(sorted(key))
+            matcher = self._special_matchers[key]
+
+            for start, end, index in matcher.find_leftmost_longest(text):
+                self._check_disallowed(text, last_index, start, disallowed_special)
+                # Encode prefix without special handling
+                prefix_spans = self._encode_ordinary_span(text, last_index, start, num_words, continuation and last_index == 0,
+                                                          max_tokens, len(spans))
+                num_words = max((word_id + 1 for _, _, word_id in prefix_spans if word_id is not None), default=num_words)
+                spans.extend(prefix_spans)
+
+                special_token = matcher.patterns[index]
+                if special_token in self.inverse_vocab:
+                    spans.append((self.inverse_vocab[special_token], (start, end), None))
+                else:
+                    raise LucidSpecialTokenNotFound(special_token).locate(text, start)
+                last_index = end
+                # Stop early rather than encoding the rest of an oversized input
+                if max_tokens is not None and len(spans) > max_tokens:
+                    raise LucidEncodeLimitExceeded("max_output_tokens", max_tokens, len(spans))
+
+        # If no special tokens or remaining text after special token split
+        self._check_disallowed(text, last_index, len(text), disallowed_special)
+        spans.extend(self._encode_ordinary_span(text, last_index, len(text), num_words, continuation and last_index == 0,
+                                                max_tokens, len(spans)))
+        if max_tokens is not None and len(spans) > max_tokens:
+            raise LucidEncodeLimitExceeded("max_output_tokens", max_tokens, len(spans))
+        return spans
+
+
+    def _encode_blank(self, text: str) -> list[tuple[int, tuple[int, int], int | None]]:
+        # Encode an empty or whitespace-only text according to TokenizerConfig.blank_input
+        policy = self.config.blank_input
+        if policy == BlankInputPolicy.ERROR:
+            raise LucidBlankInput()
+        if policy == BlankInputPolicy.SPACE and text:
+            if self.space_marker not in self.inverse_vocab:
+                raise LucidInvalidConfig(f"blank_input=SPACE needs the space token {self.space_marker!r} in the vocabulary")
+            return [(self.inverse_vocab[self.space_marker], (0, len(text)), 0)]
+        return []
+
+
+    @staticmethod
+    def _check_input_size(text: str, max_bytes: int, is_byte_text: bool) -> None:
+        # A character takes 1 to 4 UTF-8 bytes : only texts in between need encoding to be measured
+        if len(text) <= max_bytes // 4:
+            return
+        size = len(text) if is_byte_text or len(text) > max_bytes else len(text.encode("utf-8", "surrogatepass"))
+        if size > max_bytes:
+            raise LucidEncodeLimitExceeded("max_input_bytes", max_bytes, size)
+
+
+    def set_metrics(self, metrics: Metrics | None) -> None:
+        """
+        Report encode counters (texts encoded, tokens produced, errors by kind, cache hits) to a Metrics receiver
+
+        Args:
+            metrics (Metrics or None) : The receiver, e.g. InMemoryMetrics(), None to stop reporting
+        """
+
+        self.metrics = metrics
+
+
+    def metrics_snapshot(self) -> MetricsSnapshot:
+        """
+        Read the counters of an InMemoryMetrics receiver set with set_metrics
+
+        Returns:
+            MetricsSnapshot : The counter values
+        """
+
+        if not isinstance(self.metrics, InMemoryMetrics):
+            raise ValueError("metrics_snapshot requires an InMemoryMetrics receiver set with set_metrics")
+        return self.metrics.snapshot()
+
+
+    def set_encode_limits(self, limits: EncodeLimits | None) -> None:
+        """
+        Set the size limits enforced by every encode call (encode, encode_plus, batches, ...)
+
+        Inputs larger than max_input_bytes are rejected before any work is done, and encoding
+        stops with an error once more than max_output_tokens tokens are produced. Limits are a
+        runtime setting and are not saved with the tokenizer.
+
+        Args:
+            limits (EncodeLimits or None) : The limits, None to remove them
+        """
+
+        self.encode_limits = limits
+        self._shutdown_pool()
+
+
+    @staticmethod
+    def _check_disallowed(text: str, start: int, end: int, disallowed_special: set[str]) -> None:
+        # Raise if text[start:end] contains a disallowed special token
+        segment = text[start:end]
+        found = [token for token in disallowed_special if token in segment]
+        if found:
+            offset = min(segment.find(token) for token in found)
+            raise LucidDisallowedSpecialToken(sorted(found)).locate(text, start + offset)
+
+
+    def _encode_ordinary_span(self, text: str, start: int, end: int, first_word: int, continuation: bool = False,
+                              max_tokens: int | None = None,
+                              num_tokens: int = 0) -> list[tuple[int, tuple[int, int], int | None]]:
+        # Encode text[start:end] as standalone text without special token handling, stopping as soon as
+        # the num_tokens already produced plus the span's exceed max_tokens
+        spans = []
+        unk_id = self.get_special_token_id("unk")
+        num_misses = 0
+        pre_tokens = self.pre_tokenize(text, start, end, continuation)
+        for token, char_spans, word_index in pre_tokens:
+            word_id = None if word_index is None else first_word + word_index
+            # A pre-token spelling a special token is encoded from its characters like any other text
+            if token in self.inverse_vocab and token not in self._special_token_set():
+                token_ids = [self.inverse_vocab[token]]
+            else:
+                num_misses += 1
+                try:
+                    token_ids = self.tokenize_with_bpe(token)
+                except LucidCharacterNotFound as error:
+                    # Report the offset in the input text rather than in the pre-token
+                    raise error.locate(text, char_spans[error.offset][0]) from None
+
+            # Each token ID covers the next len(token) characters of the pre-token
+            position = 0
+            for token_id in token_ids:
+                length = 1 if token_id == unk_id or len(token_ids) == 1 else len(self.vocab[token_id])
+                covered = char_spans[position:position + length] if len(token_ids) > 1 else char_spans
+                spans.append((token_id, (covered[0][0], covered[-1][1]), word_id))
+                position += length
+            if max_tokens is not None and num_tokens + len(spans) > max_tokens:
+                raise LucidEncodeLimitExceeded("max_output_tokens", max_tokens, num_tokens + len(spans))
+
+        if self.metrics is not None:
+            self.metrics.increment(CACHE_HITS, len(pre_tokens) - num_misses)
+            self.metrics.increment(CACHE_MISSES, num_misses)
+        return spans
+
+
+    def pre_tokenize(self, text: str, start: int = 0, end: int | None = None,
+                     continuation: bool = False) -> list[tuple[str, list[tuple[int, int]], int | None]]:
+        """
+        Split text[start:end] into the pre-tokens fed to BPE
+
+        Lines are split on newlines and words on whitespace; every word but the very
+        first gets the space marker.
+
+        Args:
+            text (str) : The full input text
+            start (int) : Start of the span to split
+            end (int or None) : End of the span to split, defaults to the end of the text
+            continuation (bool) : The span continues a previous text on the same line, so its first
+                word gets the space marker when whitespace precedes it
+
+        Returns:
+            list[tuple[str, list[tuple[int, int]], int or None]] : Each pre-token with the character span of each
+            of its characters and the index of the word it belongs to (None for newlines)
+        """
+
+        end = len(text) if end is None else end
+        if self.config.pre_tokenizer == PreTokenizer.CODE:
+            return self.pre_tokenize_code(text, start, end, continuation)
+
+        pre_tokens = []
+        line_start = start
+        word_index = 0
+        for i, line in enumerate(text[start:end].split("\n")):
+            if i > 0:
+                pre_tokens.append(("\n", [(line_start - 1, line_start)], None))
+            for j, match in enumerate(re.finditer(r"\S+", line)):
+                word = match.group(0)
+                word_start, word_end = line_start + match.start(), line_start + match.end()
+
+                if self.config.case_markers:
+                    marker, word = self.split_case(word)
+                    if marker is not None:
+                        pre_tokens.append((marker, [(word_start, word_start)] * len(marker), word_index))
+
+                # Characters map one to one unless case folding changed the word length
+                if len(word) == word_end - word_start:
+                    char_spans = [(position, position + 1) for position in range(word_start, word_end)]
+                else:
+                    char_spans = [(word_start, word_end)] * len(word)
+
+                if self.config.word_boundary == WordBoundary.END:
+                    # The suffix is zero-width at the end of the word
+                    pieces = self._split_digits(word, char_spans, word_index)
+                    last, last_spans, _ = pieces[-1]
+                    pieces[-1] = (last + END_OF_WORD, last_spans + [(word_end, word_end)] * len(END_OF_WORD), word_index)
+                    pre_tokens.extend(pieces)
+                    word_index += 1
+                    continue
+
+                if j > 0 or i > 0 or continuation and match.start() > 0:
+                    # The marker stands for the preceding space, if there is one
+                    space = (word_start - 1, word_start) if match.start() > 0 else (word_start, word_start)
+                    word, char_spans = self.space_marker + word, [space] + char_spans
+
+                pre_tokens.extend(self._split_digits(word, char_spans, word_index))
+                word_index += 1
+            line_start += len(line) + 1
+
+        return pre_tokens
+
+
+    def pre_tokenize_code(self, text: str, start: int = 0, end: int | None = None,
+                          continuation: bool = False) -> list[tuple[str, list[tuple[int, int]], int | None]]:
+        """
+        Split text[start:end] into code pre-tokens, preserving all whitespace
+
+        Leading indentation and newline runs are kept as distinct pre-tokens, and
+        identifiers, numbers and operator runs are split apart. Spaces are written
+        as the space marker, so decoding reproduces the input exactly.
+
+        Args:
+            text (str) : The full input text
+            start (int) : Start of the span to split
+            end (int or None) : End of the span to split, defaults to the end of the text
+            continuation (bool) : The span continues a previous text on the same line, so its leading
+                whitespace is not indentation
+
+        Returns:
+            list[tuple[str, list[tuple[int, int]], int or None]] : Each pre-token with the character span of each
+            of its characters and the index of the word it belongs to (None for whitespace)
+        """
+
+        end = len(text) if end is None else end
+        pre_tokens = []
+        word_index = 0
+        # Matching from position 1 of a NUL-prefixed span keeps "^" from matching at its start
+        shift = 1 if continuation else 0
+        for match in CODE_PATTERN.finditer("\0" * shift + text[start:end], shift):
+            piece = match.group(0)
+            piece_start = start + match.start() - shift
+            char_spans = [(position, position + 1) for position in range(piece_start, piece_start + len(piece))]
+            if piece.isspace():
+                pre_tokens.append((piece.replace(" ", self.space_marker), char_spans, None))
+                continue
+
+            prefix = " " if piece.startswith(" ") else ""
+            word = piece[len(prefix):]
+            if self.config.case_markers:
+                marker, word = self.split_case(word)
+                if marker is not None:
+                    pre_tokens.append((marker, [(piece_start, piece_start)] * len(marker), word_index))
+                if len(prefix + word) != len(piece):
+                    char_spans = [(piece_start, piece_start + len(piece))] * len(prefix + word)
+
+            word = prefix.replace(" ", self.space_marker) + word
+            pre_tokens.extend(self._split_digits(word, char_spans, word_index))
+            word_index += 1
+
+        return pre_tokens
+
+
+    def _split_digits(self, word: str, char_spans: list[tuple[int, int]], word_index: int) -> list[tuple[str, list[tuple[int, int]], int]]:
+        if not self.config.split_digits:
+            return [(word, char_spans, word_index)]
+        # Every digit becomes its own pre-token
+        return [(piece.group(0), char_spans[piece.start():piece.end()], word_index) for piece in re.finditer(r"\d|\D+", word)]
+
+
+    def encode_batch(self, texts: list[str], allowed_special: set[str] | str | None = None,
+                     disallowed_special: set[str] | str | None = None, num_workers: int | None = None) -> list[list[int]]:
+        """
+        Encode several texts
+
+        Args:
+            texts (list[str]) : The input texts to encode
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in a text
+            num_workers (int or None) : Worker processes encoding large batches, defaults to get_num_threads()
+                (1 unless set, see set_num_threads)
+
+        Returns:
+            list[list[int]] : The token IDs of each text
+        """
+
+        return self._map_texts(encode_texts, texts, allowed_special, disallowed_special, num_workers)
+
+
+    def encode_batch_plus(self, texts: list[str], allowed_special: set[str] | str | None = None,
+                          disallowed_special: set[str] | str | None = None, num_workers: int | None = None) -> list[Encoding]:
+        """
+        Encode several texts into Encoding objects carrying offsets and word ids
+
+        Args:
+            texts (list[str]) : The input texts to encode
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            disallowed_special (set, "all", "none" or None) : Special tokens that raise when found in a text
+            num_workers (int or None) : Worker processes encoding large batches, defaults to get_num_threads()
+
+        Returns:
+            list[Encoding] : The encoding of each text, with source_index set to its position in texts
+        """
+
+        encodings = self._map_texts(encode_plus_texts, texts, allowed_special, disallowed_special, num_workers)
+        for index, encoding in enumerate(encodings):
+            encoding.source_index = index
+        return encodings
+
+
+    def _map_texts(self, encode_chunk: Callable, texts: list[str], allowed_special: set[str] | str | None,
+                   disallowed_special: set[str] | str | None, num_workers: int | None = None) -> list:
+        # Large batches are split into one chunk per worker and encoded in parallel
+        workers = num_workers or default_num_workers()
+        if workers <= 1 or len(texts) < 2 or sum(len(text) for text in texts) < PARALLEL_CHUNK_CHARS:
+            return encode_chunk(self, texts, allowed_special, disallowed_special)
+
+        chunk_size = -(-len(texts) // workers)
+        chunks = [texts[i:i + chunk_size] for i in range(0, len(texts), chunk_size)]
+        return self._map_chunks(workers, encode_chunk, chunks, allowed_special=allowed_special,
+                                disallowed_special=disallowed_special)
+
+
+    def _map_chunks(self, workers: int, func: Callable, chunks: list[list], **kwargs) -> list:
+        # The pool is started once, receiving the tokenizer through its initializer, and reused by later batches
+        key = (workers, dataclasses.replace(self.config))
+        with self._pool_lock:
+            if self._pool is None or self._pool_key != key:
+                self._shutdown_pool()
+                self._pool = ProcessPoolExecutor(max_workers=workers, initializer=set_worker_tokenizer, initargs=(self,))
+                self._pool_key = key
+                weakref.finalize(self, self._pool.shutdown, wait=False)
+            pool = self._pool
+        try:
+            results = list(pool.map(functools.partial(map_worker_chunk, func, **kwargs), chunks))
+        except BrokenProcessPool:
+            with self._pool_lock:
+                if self._pool is pool:
+                    self._shutdown_pool()
+            raise
+        return [result for chunk in results for result in chunk]
+
+
+    def _shutdown_pool(self) -> None:
+        # Stop the workers, whose copy of the tokenizer is out of date
+        with self._pool_lock:
+            if self._pool is not None:
+                self._pool.shutdown(wait=False)
+                self._pool = None
+                self._pool_key = None
+
+
+    def token_dtype(self) -> TokenDtype:
+        """
+        Smallest unsigned integer type able to hold every token id of the vocabulary
+
+        Returns:
+            TokenDtype : UINT16 for vocabularies of up to 65536 ids, UINT32 otherwise
+        """
+
+        return TokenDtype.UINT16 if max(self.vocab, default=0) < 1 << 16 else TokenDtype.UINT32
+
+
+    def encode_array(self, text: str, allowed_special: set[str] | None = None,
+                     dtype: TokenDtype | str | None = None) -> array.array:
+        """
+        Encode text into a compact array of unsigned integers
+
+        Args:
+            text (str) : The input text to encode
+            allowed_special (set or None) : Special tokens to allow passthrough
+            dtype (TokenDtype, str or None) : The id type, defaults to the smallest one fitting the vocabulary
+
+        Returns:
+            array.array : The token IDs
+        """
+
+        dtype = TokenDtype(dtype) if dtype is not None else self.token_dtype()
+        token_ids = self.encode(text, allowed_special)
+        if dtype == TokenDtype.UINT16 and any(token_id >= 1 << 16 for token_id in token_ids):
+            raise ValueError("Token ids do not fit in uint16 : use TokenDtype.UINT32")
+        return array.array(ARRAY_TYPECODES[dtype], token_ids)
+
+
+    def encode_numpy(self, text: str, allowed_special: set[str] | None = None, dtype: TokenDtype | str | None = None):
+        """
+        Encode text into a numpy array (requires the numpy feature)
+
+        Args:
+            text (str) : The input text to encode
+            allowed_special (set or None) : Special tokens to allow passthrough
+            dtype (TokenDtype, str or None) : The id type, defaults to the smallest one fitting the vocabulary
+
+        Returns:
+            numpy.ndarray : The token IDs
+        """
+
+        require_feature("numpy")
+        import numpy
+
+        dtype = TokenDtype(dtype) if dtype is not None else self.token_dtype()
+        return numpy.frombuffer(self.encode_array(text, allowed_special, dtype), dtype=dtype.value)
+
+
+    def encode_to_arrow(self, texts: list[str], allowed_special: set[str] | None = None):
+        """
+        Encode several texts into an Arrow record batch (requires the arrow feature)
+
+        Args:
+            texts (list[str]) : The input texts to encode
+            allowed_special (set or None) : Special tokens to allow passthrough
+
+        Returns:
+            pyarrow.RecordBatch : An "ids" list<uint32> column and a "length" uint32 column
+        """
+
+        require_feature("arrow")
+        import pyarrow
+
+        # Build the list column from flat values and offsets to avoid per-row Python lists
+        values = array.array(ARRAY_TYPECODES[TokenDtype.UINT32])
+        offsets = array.array("i", [0])
+        lengths = array.array(ARRAY_TYPECODES[TokenDtype.UINT32])
+        for text in texts:
+            token_ids = self.encode_array(text, allowed_special, TokenDtype.UINT32)
+            values.extend(token_ids)
+            offsets.append(len(values))
+            lengths.append(len(token_ids))
+
+        ids = pyarrow.ListArray.from_arrays(pyarrow.array(offsets, pyarrow.int32()), pyarrow.array(values, pyarrow.uint32()))
+        return pyarrow.RecordBatch.from_arrays([ids, pyarrow.array(lengths, pyarrow.uint32())], names=["ids", "length"])
+
+
+    def write_parquet(self, texts: Iterable[str], path: FilePath, allowed_special: set[str] | None = None,
+                      batch_size: int = 10000) -> int:
+        """
+        Encode texts and write them to a Parquet file (requires the arrow feature)
+
+        Args:
+            texts (iterable of str) : The input texts to encode, consumed in batches
+            path (str, bytes or PathLike) : Path of the Parquet file to write
+            allowed_special (set or None) : Special tokens to allow passthrough
+            batch_size (int) : Number of texts per record batch (row group)
+
+        Returns:
+            int : The number of rows written
+        """
+
+        require_feature("arrow")
+        import pyarrow.parquet
+
+        num_rows = 0
+        writer = None
+        texts = iter(texts)
+        try:
+            while True:
+                batch = [text for _, text in zip(range(batch_size), texts)]
+                if not batch:
+                    break
+                record_batch = self.encode_to_arrow(batch, allowed_special)
+                if writer is None:
+                    writer = pyarrow.parquet.ParquetWriter(fs_path(path), record_batch.schema)
+                writer.write_batch(record_batch)
+                num_rows += len(batch)
+        finally:
+            if writer is not None:
+                writer.close()
+
+        # Still produce a valid (empty) file when there are no texts
+        if writer is None:
+            pyarrow.parquet.write_table(pyarrow.Table.from_batches([self.encode_to_arrow([], allowed_special)]), fs_path(path))
+        return num_rows
+
+
+    def tokenize_file_to_bin(self, input_path: FilePath, output_path: FilePath,
+                             options: BinaryDatasetConfig | None = None) -> BinaryDatasetStats:
+        """
+        Stream a text file, encode it and write the ids to a flat binary file
+
+        The output is the raw little-endian array of token ids (the nanoGPT `.bin`
+        layout) and can be memory-mapped directly. A sidecar `<output_path>.idx`
+        holds little-endian uint64 offsets : the first token of every document,
+        followed by the total number of tokens.
+
+        Args:
+            input_path (str, bytes or PathLike) : Path of the UTF-8 text file to encode (optionally .gz, .xz or .zst)
+            output_path (str, bytes or PathLike) : Path of the binary file to write
+            options (BinaryDatasetConfig or None) : Optional dataset settings
+
+        Returns:
+            BinaryDatasetStats : Statistics about the written dataset
+        """
+
+        options = options or BinaryDatasetConfig()
+        dtype = TokenDtype(options.dtype) if options.dtype is not None else self.token_dtype()
+        eos_id = self.require_special_token_id("eos") if options.append_eos else None
+        output_path = prepare_write_path(output_path)
+        stats = BinaryDatasetStats(dtype=dtype.value, index_path=output_path + ".idx")
+
+        offsets = array.array("Q", [0])
+
+        def write_batch(documents, output):
+            token_ids = array.array(ARRAY_TYPECODES[dtype])
+            for document in documents:
+                token_ids.extend(self.encode_array(document, options.allowed_special, dtype))
+                if eos_id is not None:
+                    token_ids.append(eos_id)
+                offsets.append(stats.num_tokens + len(token_ids))
+            if sys.byteorder == "big":
+                token_ids.byteswap()
+            token_ids.tofile(output)
+            stats.num_documents += len(documents)
+            stats.num_tokens += len(token_ids)
+
+        with open_text(input_path, "r") as source, open(output_path, "wb") as output:
+            batch = []
+            for document in iter_documents(source, options.document_unit):
+                batch.append(document)
+                if len(batch) >= options.batch_documents:
+                    write_batch(batch, output)
+                    batch = []
+            if batch:
+                write_batch(batch, output)
+
+        if sys.byteorder == "big":
+            offsets.byteswap()
+        with open(stats.index_path, "wb") as index:
+            offsets.tofile(index)
+
+        return stats
+
+
+    def encode_corpus(self, reader: Iterable[str], writer: IO[bytes], format: CorpusFormat | str = CorpusFormat.JSONL,
+                      allowed_special: set[str] | str | None = None, dtype: TokenDtype | str | None = None,
+                      chunk_lines: int = 1024, max_pending: int | None = None) -> CorpusEncodeStats:
+        """
+        Encode a corpus line by line across worker processes, writing the output in input order
+
+        Lines are read lazily in chunks and at most max_pending chunks are in flight, so
+        multi-GB corpora stream through with bounded memory. Every input line gives one
+        output line (empty lines give "[]" in JSONL); the BIN format concatenates the ids.
+
+        Args:
+            reader (iterable of str) : The corpus lines, e.g. an open text file or sys.stdin
+            writer (binary file-like) : Destination of the output, e.g. open(path, "wb") or sys.stdout.buffer
+            format (CorpusFormat or str) : CorpusFormat.JSONL, CorpusFormat.TEXT or CorpusFormat.BIN
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            dtype (TokenDtype, str or None) : Id type of the BIN format, None for the smallest fitting the vocabulary
+            chunk_lines (int) : Number of lines sent to a worker at once
+            max_pending (int or None) : Maximum number of chunks encoded ahead of the writer, defaults to twice the workers
+
+        Returns:
+            CorpusEncodeStats : Number of lines encoded and token ids written
+        """
+
+        self._require_trained("encode")
+        format = CorpusFormat(format)
+        dtype = TokenDtype(dtype) if dtype is not None else self.token_dtype()
+
+        def chunks():
+            chunk = []
+            for line in reader:
+                chunk.append(line.rstrip("\r\n"))
+                if len(chunk) >= chunk_lines:
+                    yield chunk
+                    chunk = []
+            if chunk:
+                yield chunk
+
+        stats = CorpusEncodeStats()
+        workers = default_num_workers()
+        if workers <= 1:
+            # In-process, the tokenizer is passed directly instead of being installed as the worker tokenizer
+            results = (encode_corpus_lines(self, chunk, format, allowed_special, dtype) for chunk in chunks())
+        else:
+            encode_chunk = functools.partial(encode_corpus_chunk, format=format, allowed_special=allowed_special, dtype=dtype)
+            results = imap_ordered(encode_chunk, chunks(), workers, max_pending, initializer=set_worker_tokenizer,
+                                   initargs=(self,))
+        for data, num_lines, num_tokens in results:
+            writer.write(data)
+            stats.num_documents += num_lines
+            stats.num_tokens += num_tokens
+        return stats
+
+
+    def encode_padded(self, texts: list[str], max_length: int | None = None, allowed_special: set[str] | None = None,
+                      truncation: bool = False, apply_template: bool = False, return_attention_mask: bool = True,
+                      return_token_type_ids: bool = False, return_overflowing_tokens: bool = False,
+                      stride: int = 0) -> list[Encoding]:
+        """
+        Encode several texts and pad them to a common length with the pad token
+
+        Args:
+            texts (list[str]) : The input texts to encode
+            max_length (int or None) : Length to pad to, defaults to the longest encoding
+            allowed_special (set or None) : Special tokens to allow passthrough
+            truncation (bool) : Cut encodings longer than max_length instead of raising
+            apply_template (bool) : Apply the configured post-processing template to each text
+            return_attention_mask (bool) : Fill Encoding.attention_mask
+            return_token_type_ids (bool) : Fill Encoding.token_type_ids
+            return_overflowing_tokens (bool) : When truncating, emit the cut-off tokens as extra windowed encodings
+            stride (int) : Number of tokens shared by consecutive windows
+
+        Returns:
+            list[Encoding] : The padded encodings, each with the index of the text it came from
+        """
+
+        pad_id = self.require_special_token_id("pad")
+        if apply_template and self.post_processor is None:
+            raise LucidPostProcessorNotSet()
+
+        # Room left for text tokens once the template's own tokens are added
+        capacity = max_length
+        if max_length is not None and apply_template:
+            capacity = max_length - len(self.post_processor.process(self, [])[0])
+            if capacity <= 0:
+                raise ValueError(f"max_length {max_length} leaves no room for text once the template's "
+                                 f"{max_length - capacity} tokens are added")
+        if return_overflowing_tokens and capacity is not None and not 0 <= stride < capacity:
+            raise ValueError(f"stride must be between 0 and {capacity - 1} for max_length {max_length}")
+
+        encoded = []
+        for index, text in enumerate(texts):
+            token_ids = self.encode(text, allowed_special)
+            windows = [token_ids]
+            if capacity is not None and len(token_ids) > capacity:
+                if not truncation:
+                    raise ValueError(f"Encoding of length {len(token_ids)} exceeds max_length {max_length}. Enable truncation.")
+                windows = [token_ids[:capacity]]
+                if return_overflowing_tokens:
+                    step = capacity - stride
+                    windows.extend(token_ids[start:start + capacity] for start in range(step, len(token_ids) - stride, step))
+            for window in windows:
+                type_ids = [0] * len(window)
+                if apply_template:
+                    window, type_ids = self.post_processor.process(self, window)
+                encoded.append((index, window, type_ids))
+
+        length = max_length if max_length is not None else max((len(ids) for _, ids, _ in encoded), default=0)
+
+        encodings = []
+        for index, token_ids, type_ids in encoded:
+            padding = length - len(token_ids)
+            encodings.append(Encoding(
+                ids=token_ids + [pad_id] * padding,
+                attention_mask=[1] * len(token_ids) + [0] * padding if return_attention_mask else None,
+                token_type_ids=type_ids + [0] * padding if return_token_type_ids else None,
+                source_index=index,
+            ))
+
+        return encodings
+
+
+    def pack_sequences(self, encodings: list[list[int] | Encoding], max_len: int, add_eos: bool = True) -> PackedBatch:
+        """
+        Pack several short token sequences into fixed-length rows for pretraining
+
+        Each sequence (followed by the eos token) goes to the first row with enough room left,
+        rows are then padded with the pad token (eos if no pad token is set). Sequences longer
+        than max_len are split into max_len pieces first.
+
+        Args:
+            encodings (list of list[int] or Encoding) : The token sequences to pack
+            max_len (int) : Length of every packed row
+            add_eos (bool) : Append the eos token after every sequence
+
+        Returns:
+            PackedBatch : The rows with their attention mask, segment ids, positions and segment boundaries
+        """
+
+        if max_len < 1:
+            raise ValueError("max_len must be at least 1")
+        eos_id = self.require_special_token_id("eos") if add_eos else None
+        pad_id = self.get_special_token_id("pad")
+        if pad_id is None:
+            pad_id = self.require_special_token_id("eos")
+
+        # Segments as (source index, token ids), split to fit in a row
+        segments = []
+        for index, encoding in enumerate(encodings):
+            token_ids = list(encoding.ids if isinstance(encoding, Encoding) else encoding)
+            if eos_id is not None:
+                token_ids.append(eos_id)
+            segments.extend((index, token_ids[start:start + max_len]) for start in range(0, len(token_ids), max_len))
+
+        rows = []
+        free = []
+        for index, token_ids in segments:
+            row = next((row for row, room in enumerate(free) if room >= len(token_ids)), None)
+            if row is None:
+                rows.append([])
+                free.append(max_len)
+                row = len(rows) - 1
+            rows[row].append((index, token_ids))
+            free[row] -= len(token_ids)
+
+        batch = PackedBatch()
+        for row in rows:
+            ids, segment_ids, position_ids, boundaries = [], [], [], []
+            for segment, (_, token_ids) in enumerate(row):
+                boundaries.append((len(ids), len(ids) + len(token_ids)))
+                ids.extend(token_ids)
+                segment_ids.extend([segment] * len(token_ids))
+                position_ids.extend(range(len(token_ids)))
+            padding = max_len - len(ids)
+            batch.ids.append(ids + [pad_id] * padding)
+            batch.attention_mask.append([1] * len(ids) + [0] * padding)
+            batch.segment_ids.append(segment_ids + [-1] * padding)
+            batch.position_ids.append(position_ids + [0] * padding)
+            batch.boundaries.append(boundaries)
+            batch.source_indices.append([index for index, _ in row])
+        return batch
+
+
+    def encode_to_pieces(self, text: str, allowed_special: set[str] | None = None, space: str = " ") -> list[tuple[int, str]]:
+        """
+        Encode the input text and pair each token ID with its surface string
+
+        Args:
+            text (str) : The input text to encode
+            allowed_special (set or None) : Special tokens to allow passthrough
+            space (str) : String rendered in place of the space marker (e.g. " " or "␣")
+
+        Returns:
+            list[tuple[int, str]] : (token_id, piece) for every token
+        """
+
+        return [(token_id, self.vocab[token_id].replace(self.space_marker, space)) for token_id in self.encode(text, allowed_special)]
+
+
+    def tokenize(self, text: str, allowed_special: set[str] | None = None, space: str = " ") -> list[str]:
+        """
+        Split the input text into the surface strings of its tokens
+
+        Args:
+            text (str) : The input text to tokenize
+            allowed_special (set or None) : Special tokens to allow passthrough
+            space (str) : String rendered in place of the space marker
+
+        Returns:
+            list[str] : The token pieces
+        """
+
+        return [piece for _, piece in self.encode_to_pieces(text, allowed_special, space)]
+
+
+    def encode_for_model(self, text: str, add_bos: bool = True, add_eos: bool = False,
+                         allowed_special: set[str] | None = None) -> list[int]:
+        """
+        Encode the input text and wrap it with the bos/eos special tokens
+
+        Args:
+            text (str) : The input text to encode
+            add_bos (bool) : Prepend the bos token
+            add_eos (bool) : Append the eos token
+            allowed_special (set or None) : Special tokens to allow passthrough
+
+        Returns:
+            List of token IDs.
+        """
+
+        token_ids = self.encode(text, allowed_special)
+        if add_bos:
+            token_ids.insert(0, self.require_special_token_id("bos"))
+        if add_eos:
+            token_ids.append(self.require_special_token_id("eos"))
+        return token_ids
+
+
+    def register_language_tags(self, languages: list[str], template: str = "<|{}|>") -> dict[str, int]:
+        """
+        Add a language tag special token for each language, e.g. for multilingual translation models
+
+        Args:
+            languages (list[str]) : Language codes, e.g. ["en", "fr", "de"]
+            template (str) : Format of the tag token, "{}" standing for the language code
+
+        Returns:
+            dict[str, int] : The id of each language's tag token
+        """
+
+        tags = [template.format(language) for language in languages]
+        token_ids = self.add_special_tokens(tags)
+        self.language_tags.update(zip(languages, tags))
+        return dict(zip(languages, token_ids))
+
+
+    def language_tag_id(self, language: str) -> int:
+        """
+        Get the id of a registered language tag
+
+        Args:
+            language (str) : The language code ("fr") or the tag token itself ("<|fr|>")
+
+        Returns:
+            int : The tag's token id
+        """
+
+        if language in self.language_tags:
+            return self.inverse_vocab[self.language_tags[language]]
+        if language in self.language_tags.values():
+            return self.inverse_vocab[language]
+        raise LucidLanguageTagNotSet(language)
+
+
+    def register_token_group(self, name: str, tokens: list[str]) -> list[int]:
+        """
+        Name a family of special tokens, e.g. "tools" for <|tool_call|> and <|tool_result|>
+
+        The name can then stand for all of its tokens in allowed_special and disallowed_special,
+        alone (allowed_special="tools") or next to other tokens and groups ({"tools", "<|im_start|>"}).
+        Tokens missing from the vocabulary are added as special tokens; registering a name again
+        replaces its tokens.
+
+        Args:
+            name (str) : The group name, which must not be "all", "none" or a token of the group
+            tokens (list[str]) : The special tokens of the group
+
+        Returns:
+            list[int] : The ids of the group's tokens
+        """
+
+        if not isinstance(name, str) or not name or name in ("all", "none") or name in tokens:
+            raise ValueError(f"Invalid token group name {name!r}")
+        token_ids = self.add_special_tokens(list(tokens))
+        self.token_groups[name] = list(tokens)
+        return token_ids
+
+
+    def token_group(self, name: str) -> list[str]:
+        """
+        Get the special tokens of a registered group
+
+        Args:
+            name (str) : The group name
+
+        Returns:
+            list[str] : The group's tokens, in registration order
+        """
+
+        if name not in self.token_groups:
+            raise LucidTokenGroupNotSet(name)
+        return list(self.token_groups[name])
+
+
+    def encode_with_lang(self, text: str, lang_tag: str, allowed_special: set[str] | str | None = None,
+                         add_eos: bool = False) -> list[int]:
+        """
+        Encode the input text after the tag token of its language
+
+        Args:
+            text (str) : The input text to encode
+            lang_tag (str) : A registered language code ("fr") or tag token ("<|fr|>")
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            add_eos (bool) : Append the eos token
+
+        Returns:
+            List of token IDs.
+        """
+
+        token_ids = [self.language_tag_id(lang_tag)] + self.encode(text, allowed_special)
+        if add_eos:
+            token_ids.append(self.require_special_token_id("eos"))
+        return token_ids
+
+
+    def encode_with_template(self, text: str, pair: str | None = None, allowed_special: set[str] | None = None) -> list[int]:
+        """
+        Encode one or two segments and apply the configured post-processing template
+
+        Args:
+            text (str) : The first segment
+            pair (str or None) : The optional second segment
+            allowed_special (set or None) : Special tokens to allow passthrough
+
+        Returns:
+            List of token IDs.
+        """
+
+        if self.post_processor is None:
+            raise LucidPostProcessorNotSet()
+        ids_b = self.encode(pair, allowed_special) if pair is not None else None
+        token_ids, _ = self.post_processor.process(self, self.encode(text, allowed_special), ids_b)
+        return token_ids
+
+
+    def apply_chat_template(self, messages: list[tuple[str, str]], config: ChatTemplateConfig | None = None) -> list[int]:
+        """
+        Render role/content messages into token IDs using ChatML-style role markers
+
+        Args:
+            messages (list[tuple[str, str]]) : (role, content) pairs in conversation order
+            config (ChatTemplateConfig or None) : Role markers and rendering options
+
+        Returns:
+            List of token IDs.
+        """
+
+        config = config or ChatTemplateConfig()
+        rendered = []
+        for role, content in messages:
+            rendered.append(f"{config.role_start}{role}{config.separator}{content}{config.role_end}{config.separator}")
+        if config.add_generation_prompt:
+            rendered.append(f"{config.role_start}{config.assistant_role}{config.separator}")
+
+        return self.encode("".join(rendered), allowed_special={config.role_start, config.role_end})
+
+
+    def set_post_processor(self, processor: TemplateProcessor | None) -> None:
+        """
+        Set the template applied after encoding
+
+        Args:
+            processor (TemplateProcessor or None) : The template, None to remove it
+        """
+
+        self.post_processor = processor
+        self._shutdown_pool()
+
+
+    def tokenize_with_bpe(self, token: str) -> list[int]:
+        """
+        Tokenize a single token using BPE merges
+
+        Args:
+            token (str) : The token to tokenize
+
+        Return:
+            list[int] : The list of token IDs after applying BPE 
+        """
+
+        if self.config.encode_strategy == EncodeStrategy.LONGEST_MATCH:
+            return self._tokenize_longest_match(token)
+        if self.config.encode_strategy == EncodeStrategy.OPTIMAL:
+            return self._tokenize_optimal(token)
+        return self._merge_symbols(token)
+
+
+    def _merge_symbols(self, token: str, trace: list[MergeStep] | None = None) -> list[int]:
+        # Tokenize the token into individual characters
+        symbols = self._symbols(token)
+        if (self.config.word_boundary == WordBoundary.END and symbols and symbols[-1] not in self.inverse_vocab
+                and len(symbols[-1]) > len(END_OF_WORD) and symbols[-1].endswith(END_OF_WORD)):
+            # An unseen final symbol falls back to its character followed by the bare end-of-word suffix
+            symbols[-1:] = [symbols[-1][:-len(END_OF_WORD)], END_OF_WORD]
+        token_ids = [self.inverse_vocab.get(symbol, None) for symbol in symbols]
+        if None in token_ids:
+            unk_id = self.get_special_token_id("unk")
+            if unk_id is None:
+                missing_chars = [symbol for symbol, tid in zip(symbols, token_ids) if tid is None]
+                raise LucidCharacterNotFound(missing_chars, token_ids.index(None))
+            token_ids = [unk_id if tid is None else tid for tid in token_ids]
+        
+        # Merge the lowest-rank adjacent pair first, every occurrence of it in one round, as training does
+        merge_pass = 0
+        while len(token_ids) > 1:
+            pair = min(zip(token_ids, token_ids[1:]), key=lambda pair_id: self._merge_rank(pair_id, float("inf")))
+            if pair not in self.bpe_merges:
+                break
+            merged_token_id = self.bpe_merges[pair]
+            new_tokens = []
+            i = 0
+            while i < len(token_ids):
+                if i < len(token_ids) - 1 and (token_ids[i], token_ids[i+1]) == pair:
+                    if trace is not None:
+                        trace.append(MergeStep(pair=pair, new_id=merged_token_id, rank=self._merge_rank(pair),
+                                               position=len(new_tokens), merge_pass=merge_pass))
+                    new_tokens.append(merged_token_id)
+                    i += 2
+                else:
+                    new_tokens.append(token_ids[i])
+                    i += 1
+            token_ids = new_tokens
+            merge_pass += 1
+
+        return token_ids
+
+
+    def _merge_rank(self, pair: tuple[int, int], default: int | float | None = None) -> int | float:
+        if self._pair_ranks is None:
+            self._pair_ranks = {pair_id : rank for rank, pair_id in enumerate(self.bpe_merges)}
+        if default is not None:
+            return self._pair_ranks.get(pair, default)
+        return self._pair_ranks[pair]
+
+
+    def bpe_trace(self, token: str) -> list[MergeStep]:
+        """
+        List the merges applied, in order, when encoding a single pre-token with the learned merges
+
+        Merges are applied in rank order : every round merges each occurrence, left to right and
+        without overlap, of the adjacent pair with the lowest rank, until no adjacent pair has a
+        merge. This is the order training and other BPE implementations use.
+
+        Args:
+            token (str) : The pre-token in vocabulary form (e.g. "Ġhello", see pre_tokenize)
+
+        Returns:
+            list[MergeStep] : The pair, resulting id, rank, position and round of every merge applied
+        """
+
+        self._require_trained("trace")
+        trace = []
+        self._merge_symbols(token, trace)
+        return trace
+    
+
+    def decode(self, token_ids: list[int], unescape_special: bool = False) -> str:
+        """
+        Decode a list of token IDs back into a string
+
+        Args:
+            token_ids (list[int]) : The list of token IDs to decode
+            unescape_special (bool) : Undo escape_special_tokens, for ids encoded with SpecialTokenPolicy.ESCAPE
+
+        Returns:
+            str: The decoded string
+        """
+
+        text = "".join(self.decode_iter(token_ids))
+        return self.unescape_special_tokens(text) if unescape_special else text
+
+
+    def decode_batch(self, sequences: list[list[int]], num_workers: int | None = None) -> list[str]:
+        """
+        Decode several token ID sequences, in parallel for large batches, preserving input order
+
+        Args:
+            sequences (list[list[int]]) : The token ID sequences to decode
+            num_workers (int or None) : Worker processes decoding large batches, defaults to get_num_threads()
+                (1 unless set, see set_num_threads)
+
+        Returns:
+            list[str] : The decoded text of each sequence
+        """
+
+        workers = num_workers or default_num_workers()
+        if workers <= 1 or len(sequences) < 2 or sum(len(token_ids) for token_ids in sequences) < PARALLEL_DECODE_TOKENS:
+            return [self.decode(token_ids) for token_ids in sequences]
+
+        chunk_size = -(-len(sequences) // workers)
+        chunks = [sequences[i:i + chunk_size] for i in range(0, len(sequences), chunk_size)]
+        return self._map_chunks(workers, decode_sequences, chunks)
+
+
+    def decode_iter(self, token_ids: Iterable[int]) -> Iterator[str]:
+        """
+        Lazily decode token IDs into string pieces whose concatenation equals decode(token_ids)
+
+        In byte-level mode a character split across tokens is emitted once its last byte
+        is decoded, and invalid UTF-8 is replaced with U+FFFD.
+
+        Args:
+            token_ids (iterable of int) : The token IDs to decode
+
+        Yields:
+            str : The decoded piece of each token
+        """
+
+        if not self.config.byte_level:
+            yield from self._decode_pieces(token_ids)
+            return
+
+        decoder = StreamDecoder(self)
+        for token_id in token_ids:
+            text = decoder.feed(token_id)
+            if text:
+                yield text
+        text = decoder.flush()
+        if text:
+            yield text
+
+
+    def _decode_pieces(self, token_ids: Iterable[int]) -> Iterator[str]:
+        # Surface text of each token (one character per byte in byte-level mode)
+        decoder = StreamDecoder(self)
+        for token_id in token_ids:
+            piece = decoder.surface(token_id)
+            if piece:
+                yield piece
+
+
+    def stream_decoder(self) -> StreamDecoder:
+        """
+        Create an incremental decoder, for token IDs that arrive one at a time
+
+        Returns:
+            StreamDecoder : A decoder exposing feed(token_id) and flush()
+        """
+
+        return StreamDecoder(self)
+
+
+    def decode_into(self, token_ids: Iterable[int], buffer: IO[str]) -> int:
+        """
+        Decode token IDs directly into a caller-provided text buffer without building a new string
+
+        Args:
+            token_ids (iterable of int) : The token IDs to decode
+            buffer (file-like) : Any object with a write(str) method, e.g. io.StringIO or an open file
+
+        Returns:
+            int : Number of characters written
+        """
+
+        written = 0
+        for piece in self.decode_iter(token_ids):
+            buffer.write(piece)
+            written += len(piece)
+        return written
+
+
+    def decode_to_bytes(self, token_ids: Iterable[int], encoding: str = "utf-8") -> bytes:
+        """
+        Decode token IDs into encoded bytes
+
+        In byte-level mode the exact bytes are returned (even if they are not valid UTF-8)
+        and encoding is ignored.
+
+        Args:
+            token_ids (iterable of int) : The token IDs to decode
+            encoding (str) : The text encoding of the output
+
+        Returns:
+            bytes : The decoded text
+        """
+
+        if self.config.byte_level:
+            return "".join(self._decode_pieces(token_ids)).encode("latin-1")
+        return "".join(self.decode_iter(token_ids)).encode(encoding)
+
+
+    def _special_token_set(self) -> set[str]:
+        # Special tokens other than the case markers, which the pre-tokenizer itself emits
+        if self._special_set is None:
+            self._special_set = self.special_tokens()
+            if self.config.case_markers:
+                self._special_set -= {CAP_TOKEN, UPPER_TOKEN}
+        return self._special_set
+
+
+    def _regular_token_trie(self) -> VocabTrie:
+        # Trie over the vocabulary without special tokens, which only match through allowed_special
+        if self._match_trie is None:
+            special = self.special_tokens()
+            self._match_trie = VocabTrie({token_id : text for token_id, text in self.vocab.items() if text not in special})
+        return self._match_trie
+
+
+    def _tokenize_longest_match(self, token: str) -> list[int]:
+        # Take the longest regular vocabulary entry starting at each position
+        token_ids = []
+        position = 0
+        while position < len(token):
+            node, match = self._regular_token_trie().root, None
+            for end in range(position, len(token)):
+                node = node.get(token[end])
+                if node is None:
+                    break
+                if None in node:
+                    match = (end + 1, node[None])
+
+            if match is None:
+                unk_id = self.get_special_token_id("unk")
+                if unk_id is None:
+                    raise LucidCharacterNotFound([token[position]], position)
+                match = (position + 1, unk_id)
+            position, token_id = match
+            token_ids.append(token_id)
+        return token_ids
+
+
+    def _tokenize_optimal(self, token: str) -> list[int]:
+        # best[i] : (token count, merge rank total, first token id, next position) of the best segmentation of token[i:]
+        root = self._regular_token_trie().root
+        if self.config.tie_break_by_merge_rank and self._merge_ranks is None:
+            self._merge_ranks = {new_id : rank + 1 for rank, new_id in enumerate(self.bpe_merges.values())}
+
+        best = [None] * len(token) + [(0, 0, None, None)]
+        for start in range(len(token) - 1, -1, -1):
+            node = root
+            for end in range(start, len(token)):
+                node = node.get(token[end])
+                if node is None:
+                    break
+                if None in node and best[end + 1] is not None:
+                    token_id = node[None]
+                    rank = self._merge_ranks.get(token_id, 0) if self.config.tie_break_by_merge_rank else 0
+                    count, rank_total = best[end + 1][0] + 1, best[end + 1][1] + rank
+                    # Later (longer) candidates replace earlier ones on ties
+                    if best[start] is None or (count, rank_total) <= best[start][:2]:
+                        best[start] = (count, rank_total, token_id, end + 1)
+
+            if best[start] is None:
+                unk_id = self.get_special_token_id("unk")
+                if unk_id is None:
+                    raise LucidCharacterNotFound([token[start]], start)
+                count, rank_total = best[start + 1][:2]
+                best[start] = (count + 1, rank_total, unk_id, start + 1)
+
+        token_ids = []
+        position = 0
+        while position < len(token):
+            _, _, token_id, position = best[position]
+            token_ids.append(token_id)
+        return token_ids
+
+
+    def encode_sampled(self, text: str, alpha: float = 0.1, seed: int | None = None,
+                       allowed_special: set[str] | str | None = None) -> list[int]:
+        """
+        Encode with a segmentation of each word sampled among the possible ones (subword regularization)
+
+        Each word is segmented into regular vocabulary tokens with probability proportional to the
+        product of p(token) ** alpha, where p comes from the token frequencies recorded by training
+        or, without them, from the merge ranks. Small alphas give varied segmentations, large ones
+        approach the most likely segmentation. Used to augment training data : the same text
+        yields different ids that all decode to it.
+
+        Args:
+            text (str) : The input text to encode
+            alpha (float) : Smoothing exponent of the token probabilities, 0 for uniform sampling
+            seed (int or None) : Seed of the sampling, for reproducible output
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+
+        Returns:
+            List of token IDs.
+        """
+
+        if alpha < 0:
+            raise ValueError("alpha must be non-negative")
+        rng = random.Random(seed)
+        unk_id = self.get_special_token_id("unk")
+        scores = self._token_scores()
+
+        token_ids = []
+        spans = self._encode_spans(text, allowed_special)
+        start = 0
+        while start < len(spans):
+            # Group the tokens of one word, special tokens and unknown characters stay as they are
+            word_id = spans[start][2]
+            end = start + 1
+            while end < len(spans) and word_id is not None and spans[end][2] == word_id:
+                end += 1
+            word_ids = [token_id for token_id, _, _ in spans[start:end]]
+            if word_id is None or unk_id in word_ids or not all(token_id in scores for token_id in word_ids):
+                token_ids.extend(word_ids)
+            else:
+                word = "".join(self.vocab[token_id] for token_id in word_ids)
+                token_ids.extend(self._sample_segmentation(word, alpha, rng) or word_ids)
+            start = end
+        return token_ids
+
+
+    def _token_scores(self) -> dict[int, float]:
+        # Log-probability of each regular token : add-one smoothed training frequencies, else decreasing with the merge rank
+        if self._sampling_scores is None:
+            regular_ids = self._regular_token_trie().ids_with_prefix("")
+            if self.token_frequencies:
+                total = sum(self.token_frequencies.get(token_id, 0) + 1 for token_id in regular_ids)
+                self._sampling_scores = {token_id : math.log((self.token_frequencies.get(token_id, 0) + 1) / total)
+                                         for token_id in regular_ids}
+            else:
+                ranks = {new_id : rank for rank, new_id in enumerate(self.bpe_merges.values())}
+                self._sampling_scores = {token_id : -math.log(ranks.get(token_id, len(ranks)) + 2) for token_id in regular_ids}
+        return self._sampling_scores
+
+
+    def _sample_segmentation(self, word: str, alpha: float, rng: random.Random) -> list[int] | None:
+        # Forward-filtering backward-sampling over the lattice of regular tokens, None if the word has no segmentation
+        root = self._regular_token_trie().root
+        scores = self._token_scores()
+        # With end-of-word marking, the last character and the suffix form one base symbol that must not be split
+        last = len(word)
+        if self.config.word_boundary == WordBoundary.END and word.endswith(END_OF_WORD):
+            last -= len(END_OF_WORD) + 1
+
+        # forward[i] : log of the total weight of the segmentations of word[:i]; edges[i] : tokens ending at i
+        forward = [-math.inf] * (len(word) + 1)
+        forward[0] = 0.0
+        edges = [[] for _ in range(len(word) + 1)]
+        for start in range(len(word)):
+            if forward[start] == -math.inf:
+                continue
+            node = root
+            for end in range(start, len(word)):
+                node = node.get(word[end])
+                if node is None:
+                    break
+                if None in node and (end + 1 <= last or end + 1 == len(word)):
+                    weight = forward[start] + alpha * scores[node[None]]
+                    edges[end + 1].append((start, node[None], weight))
+                    high, low = max(forward[end + 1], weight), min(forward[end + 1], weight)
+                    forward[end + 1] = high + math.log1p(math.exp(low - high))
+        if forward[-1] == -math.inf:
+            return None
+
+        token_ids = []
+        position = len(word)
+        while position > 0:
+            threshold = rng.random()
+            cumulative = 0.0
+            for start, token_id, weight in edges[position]:
+                cumulative += math.exp(weight - forward[position])
+                if cumulative >= threshold:
+                    break
+            token_ids.append(token_id)
+            position = start
+        return token_ids[::-1]
+
+
+    def _symbols(self, word: str) -> list[str]:
+        # Initial BPE symbols of a word : its characters, the last one carrying the end-of-word suffix if any
+        if self.config.word_boundary == WordBoundary.END and word.endswith(END_OF_WORD) and len(word) > len(END_OF_WORD):
+            return list(word[:-len(END_OF_WORD) - 1]) + [word[-len(END_OF_WORD) - 1:]]
+        return list(word)
+
+
+    def _starts_new_word(self, token: str) -> bool:
+        # Code pre-tokens only continue a word with identifier characters
+        if self.config.pre_tokenizer == PreTokenizer.CODE:
+            return not (token[:1].isalnum() or token[:1] == "_")
+        return token == "\n" or token.startswith(self.space_marker)
+
+
+    @staticmethod
+    def split_case(word: str) -> tuple[str | None, str]:
+        """
+        Lowercase a word and return the casing marker needed to restore it
+
+        Words with mixed casing (e.g. "iPhone") are returned unchanged without a marker.
+
+        Args:
+            word (str) : The word to normalize
+
+        Returns:
+            tuple[str or None, str] : The casing marker (or None) and the word to tokenize
+        """
+
+        lowered = word.lower()
+        if lowered == word:
+            return None, word
+        cased = [i for i, char in enumerate(word) if char.lower() != char.upper()]
+        first = cased[0]
+        if word[first].isupper() and word[first + 1:] == lowered[first + 1:]:
+            return CAP_TOKEN, lowered
+        if word.isupper():
+            return UPPER_TOKEN, lowered
+        return None, word
+
+
+    @staticmethod
+    def apply_case(token: str, marker: str, space_marker: str = "Ġ") -> tuple[str, str | None]:
+        """
+        Restore casing on a token following a casing marker
+
+        Args:
+            token (str) : The token string
+            marker (str) : The active casing marker
+            space_marker (str) : The space marker, which is never re-cased
+
+        Returns:
+            tuple[str, str or None] : The cased token and the marker still pending afterwards
+        """
+
+        if marker == UPPER_TOKEN:
+            return token.upper(), marker
+        for i, char in enumerate(token):
+            if char != space_marker and char.lower() != char.upper():
+                return token[:i] + char.upper() + token[i + 1:], None
+        return token, marker
+    
+
+    def export_token_table(self) -> list[bytes]:
+        """
+        Bytes of every token, indexed by id, for inference runtimes that detokenize by table lookup
+
+        Regular tokens are given as the bytes they decode to on their own (e.g. b" world" for
+        "Ġworld"); special tokens as their UTF-8 text. Ids missing from the vocabulary map to b"".
+
+        Returns:
+            list[bytes] : The byte form of token ids 0..max id
+        """
+
+        self._require_trained("export")
+        special_tokens = self.special_tokens()
+        table = [b""] * (max(self.vocab) + 1)
+        for token_id, token in self.vocab.items():
+            if token in special_tokens:
+                table[token_id] = token.encode("utf-8")
+            elif self.config.byte_level:
+                table[token_id] = "".join(self._decode_pieces([token_id])).encode("latin-1")
+            else:
+                table[token_id] = "".join(self._decode_pieces([token_id])).encode("utf-8", "surrogatepass")
+        return table
+
+
+    def export_gguf_vocab(self, path: FilePath | IO[bytes], create_dirs: bool = False) -> None:
+        """
+        Write the vocabulary and merges as a GGUF tokenizer section (llama.cpp metadata keys, no tensors)
+
+        Tokens are written in vocabulary form, ids missing from the vocabulary as unused
+        placeholder tokens, and merges as "left right" in rank order.
+
+        Args:
+            path (str, bytes, PathLike or binary file-like) : Path or file object to write to
+            create_dirs (bool) : Create the missing parent directories of the path
+        """
+
+        self._require_trained("export")
+        special_tokens = self.special_tokens()
+        unk_token = self.special_tokens_map.get("unk")
+        tokens, token_types = [], []
+        for token_id in range(max(self.vocab) + 1):
+            token = self.vocab.get(token_id)
+            if token is None:
+                tokens.append(f"<|unused_{token_id}|>")
+                token_types.append(TOKEN_TYPE_UNUSED)
+            else:
+                tokens.append(token)
+                token_types.append(TOKEN_TYPE_UNKNOWN if token == unk_token else
+                                   TOKEN_TYPE_CONTROL if token in special_tokens else TOKEN_TYPE_NORMAL)
+
+        merges = []
+        for left, right in self.bpe_merges:
+            if " " in self.vocab[left] or " " in self.vocab[right]:
+                raise ValueError("GGUF merges are space-separated : tokens containing literal spaces cannot be exported")
+            merges.append(f"{self.vocab[left]} {self.vocab[right]}")
+        special_ids = {role : self.inverse_vocab[token] for role, token in
+                       ((role, self.special_tokens_map.get(role)) for role in ("bos", "eos", "pad", "unk")) if token is not None}
+
+        with open_binary(path, "wb", create_dirs) as file:
+            write_gguf_vocab(file, tokens, token_types, merges, special_ids)
+
+
+    def export_sentencepiece(self, path: FilePath | IO[bytes], model_type: str = "bpe", create_dirs: bool = False) -> None:
+        """
+        Write the tokenizer as a SentencePiece `.model` file (protobuf ModelProto)
+
+        The space marker is written as "▁". Piece scores are synthesized from the merge ranks
+        (earlier merges score higher, base characters lowest), which gives the merge priority
+        of a "bpe" model or the preference of a "unigram" model. SentencePiece requires an
+        unknown piece : "<unk>" is appended when no unk token is set.
+
+        Args:
+            path (str, bytes, PathLike or binary file-like) : Path or file object to write to
+            model_type (str) : "bpe" or "unigram"
+            create_dirs (bool) : Create the missing parent directories of the path
+        """
+
+        self._require_trained("export")
+        if self.config.word_boundary != WordBoundary.START or self.config.byte_level or self.config.space_marker is None:
+            raise ValueError("SentencePiece export requires space-marker word boundaries without byte-level mode")
+
+        ranks = {new_id : rank for rank, new_id in enumerate(self.bpe_merges.values())}
+        special_tokens = self.special_tokens()
+        unk_token = self.special_tokens_map.get("unk")
+        pieces = []
+        for token_id in range(max(self.vocab) + 1):
+            token = self.vocab.get(token_id)
+            if token is None:
+                pieces.append((f"<|unused_{token_id}|>", 0.0, PIECE_UNUSED))
+            elif token == unk_token:
+                pieces.append((token, 0.0, PIECE_UNKNOWN))
+            elif token in special_tokens:
+                pieces.append((token, 0.0, PIECE_CONTROL))
+            else:
+                pieces.append((token.replace(self.space_marker, SP_SPACE), -float(ranks.get(token_id, len(ranks))), PIECE_NORMAL))
+
+        special_ids = {role : self.inverse_vocab[self.special_tokens_map.get(role)]
+                       for role in ("bos", "eos", "pad", "unk") if self.special_tokens_map.get(role) is not None}
+        if unk_token is None:
+            special_ids["unk"] = len(pieces)
+            pieces.append(("<unk>", 0.0, PIECE_UNKNOWN))
+
+        data = build_model_proto(pieces, model_type, special_ids)
+        with open_binary(path, "wb", create_dirs) as file:
+            file.write(data)
+
+
+    def save_vocab_and_merges(self, vocab_path: PathOrFile, bpe_merges_path: PathOrFile, lock: bool = False,
+                              create_dirs: bool = False) -> None:
+        """
+        Saves the vocabulary and BPE merges to JSON files
+
+        Each file is written atomically (temporary file renamed over the target).
+
+        Args:
+            vocab_path (str, bytes, PathLike or file-like) : Path or file object to save vocabulary
+            bpe_merges_path (str, bytes, PathLike or file-like) : Path or file object to save the BPE merges
+            lock (bool) : Hold an advisory lock on each path while writing it, for concurrent writers
+            create_dirs (bool) : Create the missing parent directories of the paths
+        """
+
+        self._require_trained("save")
+        with open_text(vocab_path, "w", atomic=True, lock=lock, create_dirs=create_dirs) as file:
+            json.dump(self.vocab, file, ensure_ascii=False, indent=4)
+
+        with open_text(bpe_merges_path, "w", atomic=True, lock=lock, create_dirs=create_dirs) as file:
+            json.dump(self._merges_list(), file, ensure_ascii=False, indent=4)
+
+    
+    def load_vocab_and_merges(self, vocab_path: PathOrFile, bpe_merges_path: PathOrFile) -> None:
+        """
+        Load the vocabulary and BPE merges from JSON files
+
+        Args:
+            vocab_path (str, bytes, PathLike or file-like) : Path or file object of the vocabulary
+            bpe_merges_path (str, bytes, PathLike or file-like) : Path or file object of the BPE merges
+        """
+
+        with open_text(vocab_path, "r") as file:
+            loaded_vocab = json.load(file)
+
+        with open_text(bpe_merges_path, "r") as file:
+            merges_list = json.load(file)
+
+        self._load_state({"vocab" : loaded_vocab, "merges" : merges_list})
+
+
+    def to_json_str(self) -> str:
+        """
+        Serialize the tokenizer into a single JSON string
+
+        Returns:
+            str : The JSON document holding vocabulary and BPE merges
+        """
+
+        return json.dumps(self._state(), ensure_ascii=False)
+
+
+    @classmethod
+    def from_json_str(cls, data: str, expected_fingerprint: str | None = None) -> "BPETokenizer":
+        """
+        Construct a tokenizer from a JSON string produced by to_json_str
+
+        Args:
+            data (str) : The JSON document
+            expected_fingerprint (str or None) : If given, the fingerprint() the loaded tokenizer must have
+
+        Returns:
+            BPETokenizer : The loaded tokenizer
+        """
+
+        tokenizer = cls()
+        tokenizer._load_state(json.loads(data))
+        if expected_fingerprint is not None and tokenizer.fingerprint() != expected_fingerprint:
+            raise LucidFingerprintMismatch(expected_fingerprint, tokenizer.fingerprint())
+        return tokenizer
+
+
+    def save(self, path: PathOrFile, lock: bool = False, create_dirs: bool = False) -> None:
+        """
+        Save the tokenizer to a single JSON file
+
+        The file is written atomically : a temporary file in the same directory is renamed over
+        the target, so readers see either the previous or the new artifact, never a partial one.
+
+        Args:
+            path (str, bytes, PathLike or file-like) : Path or file object to write to
+            lock (bool) : Hold an advisory lock ("<path>.lock") while writing, so concurrent
+                jobs saving to the same path take turns
+            create_dirs (bool) : Create the missing parent directories of the path instead of
+                raising LucidMissingDirectory
+        """
+
+        with open_text(path, "w", atomic=True, lock=lock, create_dirs=create_dirs) as file:
+            json.dump(self._state(), file, ensure_ascii=False, indent=4)
+
+
+    @classmethod
+    def load(cls, path: PathOrFile, expected_fingerprint: str | None = None) -> "BPETokenizer":
+        """
+        Load a tokenizer saved with save
+
+        Args:
+            path (str, bytes, PathLike or file-like) : Path or file object to read from
+            expected_fingerprint (str or None) : If given, the fingerprint() the loaded tokenizer must have,
+                e.g. the one recorded in a model card
+
+        Returns:
+            BPETokenizer : The loaded tokenizer
+        """
+
+        with open_text(path, "r") as file:
+            return cls.from_json_str(file.read(), expected_fingerprint)
+
+
+    def fingerprint(self) -> str:
+        """
+        Stable content hash of the vocabulary, the ordered merges and the config
+
+        The hash only depends on the tokenizer contents, not on dictionary order or
+        on the Python or Lucid version, and is saved in every artifact.
+
+        Returns:
+            str : The hash, as "sha256:" followed by 64 hex digits
+        """
+
+        self._require_trained("fingerprint")
+        config = {key : value for key, value in self.config.to_dict().items() if FINGERPRINT_DEFAULTED_FIELDS.get(key, ...) != value}
+        data = json.dumps({
+            "vocab" : sorted(self.vocab.items()),
+            "merges" : [[*pair, new_id] for pair, new_id in self.bpe_merges.items()],
+            "config" : config,
+        }, ensure_ascii=False, sort_keys=True, separators=(",", ":"))
+        return "sha256:" + hashlib.sha256(data.encode("utf-8")).hexdigest()
+
+
+    def push_to_hub(self, repo_id: str, token: str | None = None, private: bool = False,
+                    commit_message: str = "Upload Lucid tokenizer") -> str:
+        """
+        Upload the tokenizer to a Hugging Face Hub repository (requires the hf-hub feature)
+
+        Args:
+            repo_id (str) : The repository, e.g. "user/my-tokenizer". It is created if missing
+            token (str or None) : Hub access token, defaults to the locally saved login
+            private (bool) : Create the repository as private
+            commit_message (str) : Message of the upload commit
+
+        Returns:
+            str : URL of the uploaded file
+        """
+
+        require_feature("hf-hub")
+        import huggingface_hub
+
+        api = huggingface_hub.HfApi(token=token)
+        api.create_repo(repo_id, private=private, exist_ok=True)
+        return api.upload_file(
+            path_or_fileobj=self.to_json_str().encode("utf-8"),
+            path_in_repo=HUB_FILENAME,
+            repo_id=repo_id,
+            commit_message=commit_message,
+        )
+
+
+    @classmethod
+    def from_pretrained(cls, repo_id: str, revision: str | None = None, token: str | None = None) -> "BPETokenizer":
+        """
+        Load a tokenizer from a Hugging Face Hub repository or a local directory
+
+        Downloading requires the hf-hub feature; files are cached by huggingface_hub.
+
+        Args:
+            repo_id (str) : The repository, e.g. "user/my-tokenizer", or a local directory containing the artifact
+            revision (str or None) : Branch, tag or commit to download
+            token (str or None) : Hub access token for private repositories
+
+        Returns:
+            BPETokenizer : The loaded tokenizer
+        """
+
+        if os.path.isdir(repo_id):
+            return cls.load(os.path.join(repo_id, HUB_FILENAME))
+
+        require_feature("hf-hub")
+        import huggingface_hub
+
+        path = huggingface_hub.hf_hub_download(repo_id, HUB_FILENAME, revision=revision, token=token)
+        return cls.load(path)
+
+
+    @classmethod
+    def load_shared(cls, path: FilePath) -> "BPETokenizer":
+        """
+        Load a tokenizer saved with save, sharing its vocabulary and merges with every other
+        instance loaded from an identical artifact in this process
+
+        The tables are keyed by the hash of the file content and copied on the first
+        mutation (e.g. add_special_tokens), so instances stay independent.
+
+        Args:
+            path (str, bytes or PathLike) : Path of the saved tokenizer
+
+        Returns:
+            BPETokenizer : The loaded tokenizer
+        """
+
+        with open_binary(path, "rb") as file:
+            data = file.read()
+        key = hashlib.blake2b(data, digest_size=16).hexdigest()
+
+        with _SHARED_LOCK:
+            template = _SHARED_ARTIFACTS.get(key)
+            if template is None:
+                template = _SHARED_ARTIFACTS[key] = cls.from_json_str(data.decode("utf-8"))
+        return cls._from_template(template)
+
+
+    @classmethod
+    def _from_template(cls, template: "BPETokenizer") -> "BPETokenizer":
+        # New instance sharing the vocabulary and merges of a cached template
+        tokenizer = cls(dataclasses.replace(template.config))
+        tokenizer.vocab = template.vocab
+        tokenizer.inverse_vocab = template.inverse_vocab
+        tokenizer.bpe_merges = template.bpe_merges
+        tokenizer.special_tokens_map = SpecialTokensMap.from_dict(template.special_tokens_map.to_dict())
+        if template.post_processor is not None:
+            tokenizer.post_processor = TemplateProcessor.from_dict(template.post_processor.to_dict())
+        tokenizer.reserved_id_ranges = list(template.reserved_id_ranges)
+        tokenizer._frozen = json.loads(json.dumps(template._frozen))
+        tokenizer.language_tags = dict(template.language_tags)
+        tokenizer.token_groups = {name : list(tokens) for name, tokens in template.token_groups.items()}
+        tokenizer.token_frequencies = template.token_frequencies
+        tokenizer._shared_tables = True
+        return tokenizer
+
+
+    def to_embedded(self) -> bytes:
+        """
+        Serialize the tokenizer into the binary layout read by from_embedded
+
+        Returns:
+            bytes : The artifact, e.g. to ship as package data
+        """
+
+        return pack_artifact(self._state())
+
+
+    def save_embedded(self, path: FilePath | IO[bytes], create_dirs: bool = False) -> None:
+        """
+        Save the tokenizer in the binary layout read by from_embedded
+
+        Args:
+            path (str, bytes, PathLike or binary file-like) : Path or file object to write to
+            create_dirs (bool) : Create the missing parent directories of the path
+        """
+
+        data = self.to_embedded()
+        with open_binary(path, "wb", create_dirs) as file:
+            file.write(data)
+
+
+    @classmethod
+    def from_embedded(cls, data: bytes | memoryview, expected_fingerprint: str | None = None) -> "BPETokenizer":
+        """
+        Load a tokenizer from an artifact written by to_embedded / save_embedded, already in memory
+
+        Meant for a default tokenizer shipped inside a package, e.g.
+        BPETokenizer.from_embedded(importlib.resources.files("mytool").joinpath("tokenizer.bin").read_bytes()).
+        The vocabulary and merges are stored as flat uint32 arrays and read without parsing, and,
+        as with load_shared, the tables are built once per process and shared by later calls.
+
+        Args:
+            data (bytes or memoryview) : The artifact
+            expected_fingerprint (str or None) : If given, the fingerprint() the loaded tokenizer must have
+
+        Returns:
+            BPETokenizer : The loaded tokenizer
+        """
+
+        key = "embedded:" + hashlib.blake2b(data, digest_size=16).hexdigest()
+        with _SHARED_LOCK:
+            template = _SHARED_ARTIFACTS.get(key)
+            if template is None:
+                token_ids, texts, merges, metadata = unpack_artifact(data)
+                template = cls()
+                template._load_state({**metadata, "vocab" : {}, "merges" : []})
+                template.vocab = {token_id : sys.intern(text) for token_id, text in zip(token_ids, texts)}
+                template.inverse_vocab = {text : token_id for token_id, text in template.vocab.items()}
+                template.bpe_merges = {(merges[i], merges[i + 1]) : merges[i + 2] for i in range(0, len(merges), 3)}
+                template._invalidate_caches()
+                _SHARED_ARTIFACTS[key] = template
+
+        tokenizer = cls._from_template(template)
+        if expected_fingerprint is not None and tokenizer.fingerprint() != expected_fingerprint:
+            raise LucidFingerprintMismatch(expected_fingerprint, tokenizer.fingerprint())
+        return tokenizer
+
+
+    @staticmethod
+    def clear_shared_cache() -> None:
+        """
+        Drop the artifacts cached by load_shared (existing instances keep their tables)
+        """
+
+        with _SHARED_LOCK:
+            _SHARED_ARTIFACTS.clear()
+
+
+    def _own_tables(self) -> None:
+        # Copy shared tables before mutating them in place
+        if self._shared_tables:
+            self.vocab = dict(self.vocab)
+            self.inverse_vocab = dict(self.inverse_vocab)
+            self.bpe_merges = dict(self.bpe_merges)
+            self._shared_tables = False
+
+
+    def _merges_list(self) -> list[dict]:
+        return [{"pair" : list(pair), "new_id" : new_id} for pair, new_id in self.bpe_merges.items()]
+
+
+    def _state(self) -> dict:
+        self._require_trained("save")
+        self._check_config_unchanged()
+        return {
+            "vocab" : self.vocab,
+            "merges" : self._merges_list(),
+            "special_tokens_map" : self.special_tokens_map.to_dict(),
+            "post_processor" : self.post_processor.to_dict() if self.post_processor is not None else None,
+            "reserved_id_ranges" : self.reserved_id_ranges,
+            "config" : self.config.to_dict(),
+            "frozen" : self._frozen,
+            "language_tags" : self.language_tags,
+            "token_groups" : self.token_groups,
+            "token_frequencies" : self.token_frequencies,
+            "fingerprint" : self.fingerprint(),
+        }
+
+
+    def _load_state(self, state: dict) -> None:
+        # Artifacts saved before the config was persisted keep the current settings
+        if "config" in state:
+            self.config = TokenizerConfig.from_dict(state["config"])
+            self._validated_config = dataclasses.replace(self.config)
+        # Interned token strings and ids are shared by vocab and inverse_vocab instead of duplicated
+        self.vocab = {int(k) : sys.intern(v) for k, v in state["vocab"].items()}
+        self.inverse_vocab = {v : k for k, v in self.vocab.items()}
+        ids = {token_id : token_id for token_id in self.vocab}
+        self.bpe_merges = {tuple(ids.get(i, i) for i in merge["pair"]) : ids.get(merge["new_id"], merge["new_id"]) for merge in state["merges"]}
+        self.special_tokens_map = SpecialTokensMap.from_dict(state.get("special_tokens_map", {}))
+        processor = state.get("post_processor")
+        self.post_processor = TemplateProcessor.from_dict(processor) if processor is not None else None
+        self.reserved_id_ranges = [tuple(id_range) for id_range in state.get("reserved_id_ranges", [])]
+        self._frozen = state.get("frozen")
+        self.language_tags = dict(state.get("language_tags", {}))
+        self.token_groups = {name : list(tokens) for name, tokens in state.get("token_groups", {}).items()}
+        self.token_frequencies = {int(k) : v for k, v in state.get("token_frequencies", {}).items()}
+        self._invalidate_caches()
+
+
+    def is_trained(self) -> bool:
+        """
+        Check whether the tokenizer has a vocabulary (from training or loading)
+
+        Returns:
+            bool : True if the tokenizer can encode and decode
+        """
+
+        return bool(self.vocab)
+
+
+    def _require_trained(self, operation: str) -> None:
+        if not self.is_trained():
+            raise LucidNotTrained(operation)
+
+
+    def _is_reserved(self, token_id: int) -> bool:
+        return any(start <= token_id < end for start, end in self.reserved_id_ranges)
+
+
+    def _free_id(self, start: int = 0) -> int:
+        # Smallest id from start that is neither in the vocabulary nor reserved
+        token_id = start
+        while True:
+            if token_id in self.vocab:
+                token_id += 1
+                continue
+            id_range = next(((lo, hi) for lo, hi in self.reserved_id_ranges if lo <= token_id < hi), None)
+            if id_range is None:
+                return token_id
+            token_id = id_range[1]
+
+
+    def _invalidate_caches(self) -> None:
+        self._vocab_trie = None
+        self._stop_trie = None
+        self._surface_text_index = None
+        self._nearest_index = None
+        self._match_trie = None
+        self._merge_ranks = None
+        self._pair_ranks = None
+        self._sampling_scores = None
+        self._special_matchers = {}
+        self._special_set = None
+        self._shutdown_pool()
+
+
+    def remap_ids(self, mapping: dict[int, int]) -> dict[int, int]:
+        """
+        Renumber tokens, e.g. to align the vocabulary with an existing model's embedding order
+
+        Ids missing from the mapping keep their current id. The vocabulary, the merges
+        and the word table of the latest training run are updated consistently.
+
+        Args:
+            mapping (dict[int, int]) : Mapping of current id to new id
+
+        Returns:
+            dict[int, int] : The complete old id -> new id mapping
+        """
+
+        self._check_not_frozen("renumber")
+        unknown = [token_id for token_id in mapping if token_id not in self.vocab]
+        if unknown:
+            raise ValueError(f"Token IDs not found in vocab : {unknown}")
+
+        full_mapping = {token_id : mapping.get(token_id, token_id) for token_id in self.vocab}
+        if len(set(full_mapping.values())) != len(full_mapping):
+            raise ValueError("The mapping assigns the same id to several tokens")
+
+        self.vocab = {full_mapping[token_id] : token for token_id, token in self.vocab.items()}
+        self.inverse_vocab = {token : token_id for token_id, token in self.vocab.items()}
+        self.bpe_merges = {(full_mapping[p0], full_mapping[p1]) : full_mapping[new_id] for (p0, p1), new_id in self.bpe_merges.items()}
+        self.token_frequencies = {full_mapping[token_id] : freq for token_id, freq in self.token_frequencies.items()}
+        if self._training_state is not None:
+            words = self._training_state["words"]
+            words[:] = [[full_mapping[token_id] for token_id in word] for word in words]
+
+        self._invalidate_caches()
+        return full_mapping
+
+
+    def compact_ids(self) -> dict[int, int]:
+        """
+        Renumber tokens to the contiguous range 0..len(vocab)-1, keeping their relative order
+
+        Returns:
+            dict[int, int] : The old id -> new id mapping
+        """
+
+        return self.remap_ids({token_id : new_id for new_id, token_id in enumerate(sorted(self.vocab))})
+
+
+    def trim_vocab(self, vocab_size: int) -> list[int]:
+        """
+        Shrink the vocabulary by removing the least frequent merged tokens, with their merges
+
+        Only tokens that no remaining merge builds on can be removed, so the merge table stays
+        consistent; the occurrences of a removed token are credited to its two parts, which may
+        then be removed in turn. Frequencies are those recorded by training. Removed ids are left
+        free : use compact_ids() afterwards for a contiguous range. The training state is dropped,
+        as resume_training cannot continue from a trimmed vocabulary.
+
+        Args:
+            vocab_size (int) : The target vocabulary size, special tokens included
+
+        Returns:
+            list[int] : The removed token ids, in removal order (fewer than requested if too few
+                tokens are removable)
+        """
+
+        self._require_trained("trim")
+        self._check_not_frozen("trim")
+        if not self.token_frequencies:
+            raise ValueError("trim_vocab requires the token frequencies recorded by training")
+        self._own_tables()
+
+        frequencies = dict(self.token_frequencies)
+        merge_of = {new_id : pair for pair, new_id in self.bpe_merges.items()}
+        # Number of remaining merges using each token as a part
+        uses = Counter(token_id for pair in self.bpe_merges for token_id in pair)
+        special = {self.inverse_vocab[token] for token in self.special_tokens()}
+        # Least frequent first, the latest learned token first among equals
+        heap = [(frequencies.get(token_id, 0), -token_id) for token_id in merge_of
+                if not uses[token_id] and token_id not in special]
+        heapq.heapify(heap)
+
+        removed = []
+        while heap and len(self.vocab) > vocab_size:
+            freq, token_id = heapq.heappop(heap)
+            token_id = -token_id
+            pair = merge_of.pop(token_id)
+            del self.bpe_merges[pair]
+            del self.inverse_vocab[self.vocab.pop(token_id)]
+            frequencies.pop(token_id, None)
+            removed.append(token_id)
+            for part in pair:
+                frequencies[part] = frequencies.get(part, 0) + freq
+                uses[part] -= 1
+                if part in merge_of and not uses[part] and part not in special:
+                    heapq.heappush(heap, (frequencies[part], -part))
+        self.token_frequencies = frequencies
+        self._training_state = None
+        self._invalidate_caches()
+        return removed
+
+
+    def merge_with(self, other: "BPETokenizer", strategy: VocabMergeStrategy | str = VocabMergeStrategy.APPEND) -> "BPETokenizer":
+        """
+        Combine this tokenizer with another one trained on a different domain (e.g. prose and code)
+
+        Tokens of this tokenizer keep their ids; tokens only found in the other one get the
+        next free ids. Merges of both tables are kept, a pair present in both only once.
+        Special token roles unset here are taken from the other tokenizer.
+
+        Args:
+            other (BPETokenizer) : The tokenizer to merge in, with the same TokenizerConfig
+            strategy (VocabMergeStrategy or str) : How the two merge tables are ranked against each other
+
+        Returns:
+            BPETokenizer : The combined tokenizer, neither input is modified
+        """
+
+        self._require_trained("merge")
+        other._require_trained("merge")
+        strategy = VocabMergeStrategy(strategy)
+        if self.config != other.config:
+            raise LucidInvalidConfig("Only tokenizers with the same TokenizerConfig can be merged")
+
+        merged = BPETokenizer(dataclasses.replace(self.config))
+        merged.vocab = dict(self.vocab)
+        merged.inverse_vocab = dict(self.inverse_vocab)
+        merged.reserved_id_ranges = list(self.reserved_id_ranges)
+        for token_id in sorted(other.vocab):
+            token = other.vocab[token_id]
+            if token not in merged.inverse_vocab:
+                new_id = merged._free_id(max(merged.vocab) + 1)
+                merged.vocab[new_id] = token
+                merged.inverse_vocab[token] = new_id
+
+        # (position, table, left, right, merged token) of every merge, as token strings
+        ranked = []
+        for table, tokenizer in enumerate((self, other)):
+            num_merges = len(tokenizer.bpe_merges)
+            for rank, (pair, new_id) in enumerate(tokenizer.bpe_merges.items()):
+                position = rank / num_merges if strategy == VocabMergeStrategy.INTERLEAVE else table + rank / num_merges
+                ranked.append((position, table, tokenizer.vocab[pair[0]], tokenizer.vocab[pair[1]], tokenizer.vocab[new_id]))
+
+        for _, _, left, right, new_token in sorted(ranked, key=lambda merge: merge[:2]):
+            pair = (merged.inverse_vocab[left], merged.inverse_vocab[right])
+            if pair not in merged.bpe_merges:
+                merged.bpe_merges[pair] = merged.inverse_vocab[new_token]
+
+        merged.special_tokens_map = SpecialTokensMap.from_dict(self.special_tokens_map.to_dict())
+        for role, token in other.special_tokens_map.to_dict().items():
+            if token is not None and merged.special_tokens_map.get(role) is None:
+                merged.special_tokens_map.set(role, token)
+        merged.post_processor = self.post_processor
+        merged.language_tags = {**other.language_tags, **self.language_tags}
+        merged.token_groups = {name : list(tokens) for name, tokens in {**other.token_groups, **self.token_groups}.items()}
+        # Frequencies add up over both training corpora, when both were recorded
+        if self.token_frequencies and other.token_frequencies:
+            merged.token_frequencies = dict.fromkeys(merged.vocab, 0)
+            for tokenizer in (self, other):
+                for token_id, freq in tokenizer.token_frequencies.items():
+                    merged.token_frequencies[merged.inverse_vocab[tokenizer.vocab[token_id]]] += freq
+        merged._invalidate_caches()
+        return merged
+
+
+    def freeze(self) -> None:
+        """
+        Make the current vocabulary and merges immutable
+
+        Afterwards only special tokens (add_special_tokens) and merges (add_merges) can be
+        added; they can be exported on their own with save_delta.
+        """
+
+        self._require_trained("freeze")
+        if self._frozen is None:
+            self._frozen = {"digest" : self._tables_digest(), "num_merges" : len(self.bpe_merges), "added_ids" : []}
+
+
+    def is_frozen(self) -> bool:
+        return self._frozen is not None
+
+
+    def add_merges(self, merges: list[tuple[str, str]]) -> list[int]:
+        """
+        Add merges on top of the existing ones, e.g. for a domain add-on of a frozen tokenizer
+
+        Args:
+            merges (list[tuple[str, str]]) : Token string pairs to merge, in priority order
+
+        Returns:
+            list[int] : The ids of the merged tokens
+        """
+
+        self._own_tables()
+        token_ids = []
+        for left, right in merges:
+            for part in (left, right):
+                if part not in self.inverse_vocab:
+                    raise LucidInvalidMerge(left, right, part)
+            pair = (self.inverse_vocab[left], self.inverse_vocab[right])
+            merged = left + right
+            if merged not in self.inverse_vocab:
+                new_id = self._free_id(max(self.vocab) + 1)
+                self.vocab[new_id] = merged
+                self.inverse_vocab[merged] = new_id
+                if self._frozen is not None:
+                    self._frozen["added_ids"].append(new_id)
+            if pair not in self.bpe_merges:
+                self.bpe_merges[pair] = self.inverse_vocab[merged]
+            token_ids.append(self.bpe_merges[pair])
+
+        self._invalidate_caches()
+        return token_ids
+
+
+    def save_delta(self, path: PathOrFile, create_dirs: bool = False) -> None:
+        """
+        Save only the tokens and merges added since freeze(), to be layered with load_with_delta
+
+        Args:
+            path (str, bytes, PathLike or file-like) : Path or file object to write to
+            create_dirs (bool) : Create the missing parent directories of the path
+        """
+
+        if self._frozen is None:
+            raise ValueError("Only a frozen tokenizer has a delta. Call freeze() first.")
+
+        delta = {
+            "base_digest" : self._frozen["digest"],
+            "tokens" : {token_id : self.vocab[token_id] for token_id in self._frozen["added_ids"]},
+            "merges" : self._merges_list()[self._frozen["num_merges"]:],
+            "special_tokens_map" : self.special_tokens_map.to_dict(),
+        }
+        with open_text(path, "w", atomic=True, create_dirs=create_dirs) as file:
+            json.dump(delta, file, ensure_ascii=False, indent=4)
+
+
+    @classmethod
+    def load_with_delta(cls, base: PathOrFile, delta: PathOrFile) -> "BPETokenizer":
+        """
+        Load a frozen base tokenizer and layer a delta saved with save_delta on top of it
+
+        Args:
+            base (str, PathLike or file-like) : The base tokenizer saved with save when it was frozen
+            delta (str, PathLike or file-like) : The delta artifact
+
+        Returns:
+            BPETokenizer : The frozen tokenizer with the delta applied
+        """
+
+        tokenizer = cls.load(base)
+        with open_text(delta, "r") as file:
+            delta = json.load(file)
+
+        if tokenizer._tables_digest() != delta["base_digest"]:
+            raise ValueError("The delta was created from a different base tokenizer")
+        tokenizer._frozen = {"digest" : delta["base_digest"], "num_merges" : len(tokenizer.bpe_merges), "added_ids" : []}
+
+        for token_id, token in delta["tokens"].items():
+            token_id = int(token_id)
+            if token_id in tokenizer.vocab or token in tokenizer.inverse_vocab:
+                raise ValueError(f"Delta token {token!r} conflicts with the base vocabulary")
+            tokenizer.vocab[token_id] = token
+            tokenizer.inverse_vocab[token] = token_id
+            tokenizer._frozen["added_ids"].append(token_id)
+        for merge in delta["merges"]:
+            tokenizer.bpe_merges[tuple(merge["pair"])] = merge["new_id"]
+
+        tokenizer.special_tokens_map = SpecialTokensMap.from_dict(delta.get("special_tokens_map", {}))
+        tokenizer._invalidate_caches()
+        return tokenizer
+
+
+    def _tables_digest(self) -> str:
+        # Hash of the vocabulary and the ordered merges
+        data = json.dumps([sorted(self.vocab.items()), self._merges_list()], ensure_ascii=False)
+        return hashlib.blake2b(data.encode("utf-8"), digest_size=16).hexdigest()
+
+
+    def _check_not_frozen(self, operation: str) -> None:
+        if self._frozen is not None:
+            raise LucidFrozen(operation)
+
+
+    def add_special_tokens(self, tokens: list[str]) -> list[int]:
+        """
+        Add special tokens to the vocabulary, keeping existing ids for known tokens
 
+        A token that merges can also produce from ordinary text makes decoding ambiguous;
+        recompute_closure() finds and removes such merges.
 
-    def train(self, text: str, vocab_size: int, allowed_special: set[str] = {"<|endoftext|>"}) -> None:
+        Args:
+            tokens (list[str]) : The special tokens to add
+
+        Returns:
+            list[int] : The ids of the given tokens
         """
-        Train BPE Tokenizer
+
+        if any(token not in self.inverse_vocab for token in tokens):
+            self._own_tables()
+
+        token_ids = []
+        for token in tokens:
+            if token not in self.inverse_vocab:
+                # Fill the reserved ranges first, then append after the largest id
+                reserved = (token_id for start, end in self.reserved_id_ranges for token_id in range(start, end) if token_id not in self.vocab)
+                new_id = next(reserved, None)
+                if new_id is None:
+                    new_id = self._free_id(max(self.vocab) + 1 if self.vocab else 0)
+                self.vocab[new_id] = token
+                self.inverse_vocab[token] = new_id
+                if self._frozen is not None:
+                    self._frozen["added_ids"].append(new_id)
+            token_ids.append(self.inverse_vocab[token])
+
+        self._invalidate_caches()
+        return token_ids
+
+
+    def recompute_closure(self) -> list[MergeConflict]:
+        """
+        Remove the merges that conflict with special tokens added after training
+
+        Special tokens are those listed by special_tokens(). A merge conflicts when the token it
+        produces is a special token, or decodes on its own to the same text as one : ordinary text
+        then yields the special token, or two ids decode alike. Conflicting merges are removed in
+        rank order, along with the merges built on the tokens they produced, so the result only
+        depends on the tables. Regular tokens no merge produces any more are removed from the vocabulary.
+
+        Returns:
+            list[MergeConflict] : The removed merges and tokens, empty if the tables were consistent
+        """
+
+        self._require_trained("repair")
+        special = self.special_tokens()
+        # Bytes of every token decoded on its own, special tokens being their own text
+        table = self.export_token_table()
+        special_bytes = {token.encode("utf-8", "surrogatepass") : token for token in special}
+        conflicts = []
+        for pair, new_id in self.bpe_merges.items():
+            if table[new_id] in special_bytes:
+                conflicts.append(MergeConflict(special_bytes[table[new_id]], pair, new_id))
+        if not conflicts:
+            return conflicts
+        self._check_not_frozen("repair")
+        self._own_tables()
+
+        # Number of remaining merges producing each token
+        produced = Counter(self.bpe_merges.values())
+        # Tokens no longer produced by any merge -> the conflict that made them unreachable
+        unreachable = {}
+
+        def remove_merge(pair: tuple[int, int], conflict: MergeConflict) -> None:
+            new_id = self.bpe_merges.pop(pair)
+            produced[new_id] -= 1
+            if not produced[new_id]:
+                unreachable.setdefault(new_id, conflict)
+
+        for conflict in conflicts:
+            remove_merge(conflict.pair, conflict)
+        # Merges built on unreachable tokens can no longer apply, until no merge uses one
+        changed = True
+        while changed:
+            changed = False
+            for pair in [pair for pair in self.bpe_merges if pair[0] in unreachable or pair[1] in unreachable]:
+                remove_merge(pair, unreachable.get(pair[0]) or unreachable[pair[1]])
+                changed = True
+
+        # Special tokens stay, as they are matched through allowed_special rather than produced by merges
+        for token_id, conflict in sorted(unreachable.items()):
+            if self.vocab[token_id] not in special:
+                del self.inverse_vocab[self.vocab.pop(token_id)]
+                conflict.removed_ids.append(token_id)
+        if self.token_frequencies:
+            self.token_frequencies = {token_id : freq for token_id, freq in self.token_frequencies.items()
+                                      if token_id in self.vocab}
+        self._training_state = None
+        self._invalidate_caches()
+        return conflicts
+
+
+    def set_special_token(self, role: str, token: str | None) -> None:
+        """
+        Assign a special token to a named role, adding it to the vocabulary if needed
 
         Args:
-            text (str) : The text used to train the tokenizer
-            vocab_size (int) : The vocabulary size
-            allowed_special (set) : A set of included special tokens
+            role (str) : One of "bos", "eos", "pad", "unk"
+            token (str or None) : The special token, None to unset the role
         """
 
-        # Replace space with "Ġ"
-        processed_text = []
-        for i, char in enumerate(text):
-            if char == " " and i != 0:
-                processed_text.append("Ġ")
-            if char != " ":
-                processed_text.append(char)
-        processed_text = "".join(processed_text)
+        SpecialTokensMap.check_role(role)
+        if token is not None:
+            self.add_special_tokens([token])
+        self.special_tokens_map.set(role, token)
+        self._invalidate_caches()
 
-        # Initialize vocab with unique characters
-        unique_chars = [chr(i) for i in range(256)]
-        unique_chars.extend(char for char in sorted(set(processed_text)) if char not in unique_chars)
-        if "Ġ" not in unique_chars:
-            unique_chars.append("Ġ")
-        
-        self.vocab = {i : char for i, char in enumerate(unique_chars)}
-        self.inverse_vocab = {char : i for i, char in self.vocab.items()}
 
-        # Add special tokens
-        if allowed_special:
-            for token in allowed_special:
-                if token not in self.inverse_vocab:
-                    new_id = len(self.vocab)
-                    self.vocab[new_id] = token
-                    self.inverse_vocab[token] = new_id
+    def get_special_token(self, role: str) -> str | None:
+        """
+        Get the special token assigned to a role
 
-        # Tokenize the text
-        token_ids = [self.inverse_vocab[char] for char in processed_text]
+        Args:
+            role (str) : One of "bos", "eos", "pad", "unk"
 
-        # Find and Replace frequent pairs
-        for new_id in range(len(self.vocab), vocab_size):
-            pair_id = self.find_freq_pair(token_ids, mode="most")
-            if pair_id is None:
-                break
-            token_ids = self.replace_pair(token_ids, pair_id, new_id)
-            self.bpe_merges[pair_id] = new_id
-        
-        # Build the vocabulary with the merged tokens
-        for (p0, p1), new_id in self.bpe_merges.items():
-            merged_token = self.vocab[p0] + self.vocab[p1]
-            self.vocab[new_id] = merged_token
-            self.inverse_vocab[merged_token] = new_id
+        Returns:
+            str or None : The special token, None if the role is unset
+        """
+
+        return self.special_tokens_map.get(role)
 
 
-    def encode(self, text: str, allowed_special: set[str] | None = None) -> list[int]:
+    def get_special_token_id(self, role: str) -> int | None:
         """
-        Encode the input text into a list of token IDs
+        Get the id of the special token assigned to a role
 
         Args:
-            text (str) : The input text to encode
-            allowed_special (set or None) : Special tokens to allow passthrough
+            role (str) : One of "bos", "eos", "pad", "unk"
 
         Returns:
-            List of token IDs.
+            int or None : The token id, None if the role is unset
         """
 
-        token_ids = []
-        if allowed_special is not None and len(allowed_special) > 0:
-            # Build regex to match allowed special tokens
-            special_pattern = ("(" + "|".join(re.escape(tok) for tok in sorted(allowed_special, key=len, reverse=True)) + ")")
+        token = self.special_tokens_map.get(role)
+        return None if token is None else self.inverse_vocab.get(token)
 
-            last_index = 0
-            for match in re.finditer(special_pattern, text):
-                prefix = text[last_index:match.start()]
-                # Encode prefix without special handling
-                token_ids.extend(self.encode(prefix, allowed_special=None))
 
-                special_token = match.group(0)
-                if special_token in self.inverse_vocab:
-                    token_ids.append(self.inverse_vocab[special_token])
-                else:
-                    raise ValueError(f"Special token {special_token} not found in vocabulary.")
-                last_index = match.end()
-            
-            # Remaining part to process normally
-            text = text[last_index:]
+    def require_special_token_id(self, role: str) -> int:
+        token_id = self.get_special_token_id(role)
+        if token_id is None:
+            raise LucidSpecialTokenNotSet(role)
+        return token_id
 
-            # Check if any disallowed special tokens are in the remainder
-            disallowed = [
-                tok for tok in self.inverse_vocab 
-                if tok.startswith("<|") and tok.endswith("|>") and tok in text and tok not in allowed_special
-            ]
 
-            if disallowed:
-                raise ValueError(f"Disallowed special tokens encounterd in text : {disallowed}")
+    def coverage_report(self, texts: list[str]) -> CoverageReport:
+        """
+        Measure how well the vocabulary covers a target dataset
 
-        # If no special tokens or remaining text after special token split
-        tokens = []
-        lines = text.split("\n")
-        for i, line in enumerate(lines):
-            if i > 0:
-                tokens.append("\n")
-            words = line.split()
-            for j, word in enumerate(words):
-                if j == 0 and i > 0:
-                    tokens.append("Ġ" + word)
-                elif j == 0:
-                    tokens.append(word)
-                else:
-                    tokens.append("Ġ" + word)
+        Args:
+            texts (list[str]) : Sample texts from the target domain
 
-        for token in tokens:
-            if token in self.inverse_vocab:
-                token_ids.append(self.inverse_vocab[token])
-            else:
-                token_ids.extend(self.tokenize_with_bpe(token))
+        Returns:
+            CoverageReport : Missing characters, unknown words, single-character token share and OOV rate
+        """
 
-        return token_ids
-            
+        report = CoverageReport()
+        missing_chars = Counter()
+        unknown_words = Counter()
+        single_char_tokens = 0
+        oov_words = 0
+        unk_id = self.get_special_token_id("unk")
 
-    def tokenize_with_bpe(self, token: str) -> list[int]:
+        for text in texts:
+            for token, _, word_index in self.pre_tokenize(text):
+                if word_index is None or token in (CAP_TOKEN, UPPER_TOKEN):
+                    continue
+                report.total_words += 1
+                if token in self.inverse_vocab:
+                    report.total_tokens += 1
+                    single_char_tokens += len(token.removeprefix(self.space_marker)) <= 1
+                    continue
+
+                oov_words += 1
+                missing = [symbol for symbol in self._symbols(token) if symbol not in self.inverse_vocab]
+                if missing:
+                    missing_chars.update(missing)
+                    unknown_words[token.removeprefix(self.space_marker)] += 1
+                    if unk_id is None:
+                        continue
+                token_ids = self.tokenize_with_bpe(token)
+                report.total_tokens += len(token_ids)
+                single_char_tokens += sum(1 for token_id in token_ids if len(self.vocab[token_id].removeprefix(self.space_marker)) <= 1)
+
+        report.missing_chars = dict(missing_chars.most_common())
+        report.unknown_words = dict(unknown_words.most_common())
+        report.single_char_token_ratio = single_char_tokens / report.total_tokens if report.total_tokens else 0.0
+        report.oov_rate = oov_words / report.total_words if report.total_words else 0.0
+        return report
+
+
+    def evaluate(self, held_out_text: str) -> EvalMetrics:
         """
-        Tokenize a single token using BPE merges
+        Measure compression and word segmentation quality on unseen text
 
         Args:
-            token (str) : The token to tokenize
+            held_out_text (str) : Text that was not used for training
 
-        Return:
-            list[int] : The list of token IDs after applying BPE 
+        Returns:
+            EvalMetrics : Tokens per byte, fertility and whole-word token ratio
         """
 
-        # Tokenize the token into individual characters
-        token_ids = [self.inverse_vocab.get(char, None) for char in token]
-        if None in token_ids:
-            missing_chars = [char for char, tid in zip(token, token_ids) if tid is None]
-            raise ValueError(f"Characters not found in vocab : {missing_chars}")
-        
-        can_merge = True
-        while can_merge and len(token_ids) > 1:
-            can_merge = False
-            new_tokens = []
-            i = 0
-            while i < len(token_ids) - 1:
-                pair = (token_ids[i], token_ids[i+1])
-                if pair in self.bpe_merges:
-                    merged_token_id = self.bpe_merges[pair]
-                    new_tokens.append(merged_token_id)
-                    i += 2
-                    can_merge = True
+        encoding = self.encode_plus(held_out_text)
+        tokens_per_word = Counter(word_id for word_id in encoding.word_ids if word_id is not None)
+
+        metrics = EvalMetrics(num_tokens=len(encoding.ids), num_words=len(tokens_per_word))
+        num_bytes = len(held_out_text.encode("utf-8"))
+        metrics.tokens_per_byte = metrics.num_tokens / num_bytes if num_bytes else 0.0
+        if metrics.num_words:
+            metrics.fertility = sum(tokens_per_word.values()) / metrics.num_words
+            metrics.whole_word_ratio = sum(1 for count in tokens_per_word.values() if count == 1) / metrics.num_words
+        return metrics
+
+
+    def compare(self, other: "BPETokenizer", corpus: str | Iterable[str], allowed_special: set[str] | str | None = None,
+                num_examples: int = 5) -> ComparisonReport:
+        """
+        Compare this tokenizer with another on a corpus, e.g. before migrating a model to a new vocabulary
+
+        Vocabularies are compared by the bytes their regular tokens decode to, so tokenizers with
+        different space markers or byte-level settings still share their common tokens.
+
+        Args:
+            other (BPETokenizer) : The tokenizer to compare with ("b" in the report)
+            corpus (str or iterable of str) : The corpus text, or an iterable of lines (e.g. an open file)
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+            num_examples (int) : Number of most divergent lines kept in the report
+
+        Returns:
+            ComparisonReport : Token counts, tokens per byte, vocabulary overlap and the most divergent lines
+        """
+
+        self._require_trained("compare")
+        other._require_trained("compare")
+        report = ComparisonReport(vocab_size_a=len(self.vocab), vocab_size_b=len(other.vocab))
+        surfaces = []
+        for tokenizer in (self, other):
+            table = tokenizer.export_token_table()
+            surfaces.append({table[token_id] for token_id in tokenizer._regular_token_trie().ids_with_prefix("")})
+        report.shared_tokens = len(surfaces[0] & surfaces[1])
+        distinct = len(surfaces[0] | surfaces[1])
+        report.vocab_overlap = report.shared_tokens / distinct if distinct else 0.0
+
+        # Min-heap of the most divergent lines, ties kept for the earliest line
+        examples = []
+        lines = corpus.split("\n") if isinstance(corpus, str) else corpus
+        for line_number, line in enumerate(lines, 1):
+            line = line.rstrip("\n")
+            try:
+                spans_a = self.encode_with_offsets(line, allowed_special)
+                spans_b = other.encode_with_offsets(line, allowed_special)
+            except LucidError:
+                report.num_failed += 1
+                continue
+
+            report.num_lines += 1
+            report.num_bytes += len(line.encode("utf-8", "surrogatepass"))
+            report.num_tokens_a += len(spans_a)
+            report.num_tokens_b += len(spans_b)
+            boundaries_a = {end for _, (_, end) in spans_a}
+            boundaries_b = {end for _, (_, end) in spans_b}
+            boundaries = boundaries_a | boundaries_b
+            divergence = 1 - len(boundaries_a & boundaries_b) / len(boundaries) if boundaries else 0.0
+            if divergence > 0 and num_examples > 0:
+                example = DivergentText(line_number, line, divergence, [line[start:end] for _, (start, end) in spans_a],
+                                        [line[start:end] for _, (start, end) in spans_b])
+                if len(examples) < num_examples:
+                    heapq.heappush(examples, (divergence, -line_number, example))
                 else:
-                    new_tokens.append(token_ids[i])
-                    i += 1
-            if i < len(token_ids):
-                new_tokens.append(token_ids[i])
-            token_ids = new_tokens
+                    heapq.heappushpop(examples, (divergence, -line_number, example))
+
+        if report.num_bytes:
+            report.tokens_per_byte_a = report.num_tokens_a / report.num_bytes
+            report.tokens_per_byte_b = report.num_tokens_b / report.num_bytes
+        report.examples = [example for _, _, example in sorted(examples, reverse=True)]
+        return report
 
-        return token_ids
-    
 
-    def decode(self, token_ids: list[int]) -> str:
+    def validate_roundtrip(self, corpus: str | Iterable[str], allowed_special: set[str] | None = None,
+                           max_mismatches: int = 100) -> ValidationReport:
         """
-        Decode a list of token IDs back into a string
+        Encode and decode every line of a corpus and report the lines that do not come back unchanged
 
         Args:
-            token_ids (list[int]) : The list of token IDs to decode
+            corpus (str or iterable of str) : The corpus text, or an iterable of lines (e.g. an open file)
+            allowed_special (set or None) : Special tokens to allow passthrough
+            max_mismatches (int) : Maximum number of mismatching lines kept in the report
 
         Returns:
-            str: The decoded string
+            ValidationReport : Line counts and details of the mismatches
+        """
+
+        lines = corpus.split("\n") if isinstance(corpus, str) else corpus
+        report = ValidationReport()
+        for line_number, line in enumerate(lines, 1):
+            line = line.rstrip("\n")
+            report.num_lines += 1
+            try:
+                decoded = self.decode(self.encode(line, allowed_special))
+            except LucidError as error:
+                # Lines that cannot be encoded at all are reported with the error
+                report.num_mismatches += 1
+                if len(report.mismatches) < max_mismatches:
+                    offset = error.offset or 0
+                    report.mismatches.append(RoundTripMismatch(
+                        line_number, line, "", offset, len(line[:offset].encode("utf-8", "surrogatepass")), [str(error)],
+                    ))
+                continue
+            if decoded == line:
+                continue
+
+            report.num_mismatches += 1
+            if len(report.mismatches) < max_mismatches:
+                matcher = difflib.SequenceMatcher(None, line, decoded, autojunk=False)
+                diff = [f"{tag} {line[i1:i2]!r} -> {decoded[j1:j2]!r} at {i1}"
+                        for tag, i1, i2, j1, j2 in matcher.get_opcodes() if tag != "equal"]
+                offset = next(i1 for tag, i1, _, _, _ in matcher.get_opcodes() if tag != "equal")
+                report.mismatches.append(RoundTripMismatch(
+                    line_number, line, decoded, offset, len(line[:offset].encode("utf-8", "surrogatepass")), diff,
+                ))
+        return report
+
+
+    def explain(self, text: str, allowed_special: set[str] | str | None = None) -> list[TokenExplanation]:
+        """
+        Describe how every token of an encoding was produced, to debug unexpected tokenizations
+
+        Args:
+            text (str) : The input text
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+
+        Returns:
+            list[TokenExplanation] : Id, surface text, span, merge chain and merge rank of each token
+        """
+
+        # Merge producing each token id (the earliest one if several pairs give the same token)
+        producers = {}
+        for rank, (pair, new_id) in enumerate(self.bpe_merges.items()):
+            producers.setdefault(new_id, (rank, pair))
+
+        def merge_chain(token_id, chain):
+            if token_id in producers:
+                left, right = producers[token_id][1]
+                merge_chain(left, chain)
+                merge_chain(right, chain)
+                chain.append(f"{self.vocab[left]} + {self.vocab[right]} -> {self.vocab[token_id]}")
+            return chain
+
+        special_tokens = self.special_tokens()
+        explanations = []
+        for token_id, offsets, _ in self._encode_spans(text, allowed_special):
+            explanations.append(TokenExplanation(
+                token_id=token_id,
+                token=self.vocab[token_id],
+                surface=self.decode([token_id]),
+                offsets=offsets,
+                merge_chain=merge_chain(token_id, []),
+                rank=producers[token_id][0] if token_id in producers else None,
+                special=self.vocab[token_id] in special_tokens,
+            ))
+        return explanations
+
+
+    def visualize(self, text: str, format: VisualizationFormat | str = VisualizationFormat.HTML,
+                  allowed_special: set[str] | str | None = None) -> str:
+        """
+        Render the text with alternating background colors marking the token boundaries
+
+        The "html" rendering shows the id and vocabulary entry of each token on hover and is
+        displayed inline when it is the last value of a notebook cell. Byte-level tokens that
+        split a character are shown as one colored span listing all their ids.
+
+        Args:
+            text (str) : The input text
+            format (VisualizationFormat or str) : "html" or "ansi" (terminal colors)
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough
+
+        Returns:
+            str : The rendering
+        """
+
+        spans = self._encode_spans(text, allowed_special)
+        if self.config.byte_level:
+            # Offsets are UTF-8 byte offsets : widen them to the characters they touch
+            boundaries = [0]
+            for char in text:
+                boundaries.append(boundaries[-1] + len(char.encode("utf-8", "surrogatepass")))
+            spans = [(token_id, (bisect.bisect_right(boundaries, start) - 1, bisect.bisect_left(boundaries, end)), word_id)
+                     for token_id, (start, end), word_id in spans]
+        return render_tokens(text, [(token_id, self.vocab[token_id], start, end) for token_id, (start, end), _ in spans], format)
+
+
+    def record_reference(self, texts: list[str], cases_path: PathOrFile, allowed_special: set[str] | str | None = None) -> None:
+        """
+        Record the current encoding of some texts as reference cases for verify_against_reference
+
+        Args:
+            texts (list[str]) : The texts to record
+            cases_path (str, PathLike or file-like) : Path or file object of the JSON cases file to write
+            allowed_special (set, "all", "none" or None) : Special tokens to allow passthrough, recorded with each case
+        """
+
+        allowed = sorted(allowed_special) if isinstance(allowed_special, (set, frozenset, list)) else allowed_special
+        cases = [{"text" : text, "allowed_special" : allowed, "ids" : self.encode(text, allowed_special)} for text in texts]
+        with open_text(cases_path, "w", atomic=True) as file:
+            json.dump({"fingerprint" : self.fingerprint(), "cases" : cases}, file, ensure_ascii=False, indent=4)
+
+
+    def verify_against_reference(self, cases_path: PathOrFile, max_mismatches: int = 100) -> ReferenceReport:
+        """
+        Check that encode still produces the token ids recorded in a reference cases file
+
+        The file holds {"fingerprint": ..., "cases": [{"text", "allowed_special", "ids"}, ...]},
+        as written by record_reference, so tokenization can be pinned across releases.
+
+        Args:
+            cases_path (str, PathLike or file-like) : Path or file object of the JSON cases file
+            max_mismatches (int) : Maximum number of mismatching cases kept in the report
+
+        Returns:
+            ReferenceReport : Case counts, details of the mismatches and whether the fingerprint matches
+        """
+
+        with open_text(cases_path, "r") as file:
+            reference = json.load(file)
+
+        report = ReferenceReport()
+        if reference.get("fingerprint") is not None:
+            report.fingerprint_matches = reference["fingerprint"] == self.fingerprint()
+        for index, case in enumerate(reference["cases"]):
+            report.num_cases += 1
+            allowed_special = case.get("allowed_special")
+            if isinstance(allowed_special, list):
+                allowed_special = set(allowed_special)
+            try:
+                actual, error = self.encode(case["text"], allowed_special), None
+            except LucidError as exception:
+                actual, error = None, str(exception)
+            if actual == case["ids"]:
+                continue
+
+            report.num_mismatches += 1
+            if len(report.mismatches) < max_mismatches:
+                first_difference = next((i for i, (a, b) in enumerate(zip(case["ids"], actual or [])) if a != b),
+                                        min(len(case["ids"]), len(actual or [])))
+                report.mismatches.append(ReferenceMismatch(index, case["text"], case["ids"], actual, first_difference, error))
+        return report
+
+
+    def vocab_stats(self, num_longest: int = 20) -> VocabStats:
+        """
+        Summarize the vocabulary to surface pathological merges such as very long tokens
+
+        Args:
+            num_longest (int) : Number of longest tokens to report
+
+        Returns:
+            VocabStats : Length distribution, whitespace and non-ASCII counts, and the longest tokens
         """
 
-        decoded_string = ""
-        for i, token_id in enumerate(token_ids):
-            if token_id not in self.vocab:
-                raise ValueError(f"Token ID {token_id} not found in vocab")
-            token = self.vocab[token_id]
-            if token == "\n":
-                if decoded_string and not decoded_string.endswith(" "):
-                    decoded_string += " "
-                decoded_string += token
-            elif token.startswith("Ġ"):
-                decoded_string += " " + token[1:]
+        stats = VocabStats()
+        regular = []
+        for token_id, token in self.vocab.items():
+            if token.startswith("<|") and token.endswith("|>"):
+                stats.num_special_tokens += 1
             else:
-                decoded_string += token
+                regular.append((token_id, token))
+
+        lengths = Counter(len(token) for _, token in regular)
+        stats.num_tokens = len(regular)
+        stats.length_histogram = dict(sorted(lengths.items()))
+        stats.mean_length = sum(length * count for length, count in lengths.items()) / len(regular) if regular else 0.0
+        stats.num_with_space_marker = sum(1 for _, token in regular if self.space_marker in token)
+        stats.num_non_ascii = sum(1 for _, token in regular if not token.isascii())
+        stats.longest_tokens = sorted(regular, key=lambda item: (-len(item[1]), item[0]))[:num_longest]
+        return stats
 
-        return decoded_string
-    
 
-    def save_vocab_and_merges(self, vocab_path: str, bpe_merges_path: str) -> None:
+    def audit_vocab(self, kinds: Iterable[VocabIssueKind | str] | None = None) -> list[VocabIssue]:
         """
-        Saves the vocabulary and BPE merges to JSON files
+        Find groups of near-duplicate regular tokens, which waste vocabulary slots
+
+        Many case variants or NFKC-equivalent tokens suggest the corpus should have been
+        normalized (or case_markers enabled) before training. Tokens are compared by their
+        decoded text; single characters (the base alphabet) and byte-level tokens holding
+        partial UTF-8 sequences are skipped.
 
         Args:
-            vocab_path (str) : Path to save vocabulary
-            bpe_merges_path (str) : Path to save the BPE merges
+            kinds (iterable of VocabIssueKind or str, or None) : Kinds of issues to look for, all if None
+
+        Returns:
+            list[VocabIssue] : One issue per group of at least two distinct tokens, by kind, then by lowest token id
         """
 
-        with open(vocab_path, "w", encoding="utf-8") as file:
-            json.dump(self.vocab, file, ensure_ascii=False, indent=4)
+        self._require_trained("audit")
+        kinds = list(VocabIssueKind) if kinds is None else [VocabIssueKind(kind) for kind in kinds]
+        decoded = {token_id : self.decode([token_id]) for token_id in self._regular_token_trie().ids_with_prefix("")}
+        decoded = {token_id : text for token_id, text in decoded.items() if len(text) > 1 and "\ufffd" not in text}
+        normalizers = {
+            VocabIssueKind.CASE_VARIANT : str.lower,
+            VocabIssueKind.NFKC_EQUIVALENT : lambda text: unicodedata.normalize("NFKC", text),
+            VocabIssueKind.SPACE_MARKER_VARIANT : lambda text: text.strip(" "),
+        }
 
-        with open(bpe_merges_path, "w", encoding="utf-8") as file:
-            merges_list = [{"pair" : list(pair), "new_id" : new_id} for pair, new_id in self.bpe_merges.items()]
-            json.dump(merges_list, file, ensure_ascii=False, indent=4)
+        issues = []
+        for kind in kinds:
+            groups = {}
+            for token_id in sorted(decoded):
+                key = normalizers[kind](decoded[token_id])
+                if key.strip():
+                    groups.setdefault(key, []).append(token_id)
+            for key, token_ids in groups.items():
+                if len({decoded[token_id] for token_id in token_ids}) > 1:
+                    issues.append(VocabIssue(kind.value, key, token_ids, [decoded[token_id] for token_id in token_ids]))
+        return sorted(issues, key=lambda issue: (kinds.index(VocabIssueKind(issue.kind)), issue.token_ids[0]))
 
-    
-    def load_vocab_and_merges(self, vocab_path: str, bpe_merges_path: str) -> None:
+
+    def memory_usage(self) -> MemoryReport:
         """
-        Load the vocabulary and BPE merges from JSON files
+        Report the approximate memory used by the tokenizer's data structures
+
+        Objects shared between structures are counted once, in the first one listed.
+
+        Returns:
+            MemoryReport : Bytes used by vocab, inverse vocab, merges, caches and training state
+        """
+
+        seen = set()
+        report = MemoryReport(
+            vocab_bytes=deep_sizeof(self.vocab, seen),
+            inverse_vocab_bytes=deep_sizeof(self.inverse_vocab, seen),
+            merges_bytes=deep_sizeof(self.bpe_merges, seen),
+            caches_bytes=deep_sizeof(self._vocab_trie, seen) if self._vocab_trie is not None else 0,
+            training_state_bytes=deep_sizeof(self._training_state, seen) if self._training_state is not None else 0,
+        )
+        report.total_bytes = (report.vocab_bytes + report.inverse_vocab_bytes + report.merges_bytes
+                              + report.caches_bytes + report.training_state_bytes)
+        return report
+
+
+    def build_vocab_trie(self) -> VocabTrie:
+        """
+        Build (or return the cached) prefix trie over the vocabulary
+
+        Returns:
+            VocabTrie : The trie mapping token strings to ids
+        """
+
+        if self._vocab_trie is None:
+            self._vocab_trie = VocabTrie(self.vocab)
+        return self._vocab_trie
+
+
+    def tokens_with_prefix(self, prefix: str) -> list[int]:
+        """
+        Enumerate the token ids whose token string starts with a prefix
 
         Args:
-            vocab_path (str) : Path to the vocabulary file
-            bpe_merges_path (str) : Path to the BPE merges file
+            prefix (str) : The prefix, in the vocabulary's own form (spaces written as the space marker)
+
+        Returns:
+            list[int] : Sorted token ids continuing the prefix
         """
 
-        with open(vocab_path, "r", encoding="utf-8") as file:
-            loaded_vocab = json.load(file)
-            self.vocab = {int(k) : v for k, v in loaded_vocab.items()}
-            self.inverse_vocab = {v : int(k) for k, v in loaded_vocab.items()}
+        return self.build_vocab_trie().ids_with_prefix(prefix)
+
+
+    def compile_stop_sequences(self, stop_sequences: list[str]) -> StopMatcher:
+        """
+        Compile stop strings into an incremental matcher for generation
+
+        Args:
+            stop_sequences (list[str]) : The stop strings to detect
+
+        Returns:
+            StopMatcher : A matcher exposing feed(token_id), handling stop strings that span token boundaries
+        """
+
+        return StopMatcher(self, stop_sequences)
+
+
+    def find_stop(self, ids_or_text: str | Iterable[int], stop_sequences: list[str]) -> StopHit | None:
+        """
+        Find the first stop string completed in a text or a sequence of generated token IDs
+
+        Args:
+            ids_or_text (str or iterable of int) : The generated text or token IDs
+            stop_sequences (list[str]) : The stop strings
+
+        Returns:
+            StopHit or None : The earliest-completed stop string, with the index of the completing token for IDs
+        """
+
+        if isinstance(ids_or_text, str):
+            return find_stop(ids_or_text, stop_sequences)
+
+        matcher = StopMatcher(self, stop_sequences)
+        for token_id in ids_or_text:
+            hit = matcher.feed(token_id)
+            if hit is not None:
+                return hit
+        return None
+
+
+    def token_ids_for_stop_sequences(self, stop_sequences: list[str], max_variants: int = 16) -> list[list[int]]:
+        """
+        Precompute every token ID sequence whose decoded text is exactly one of the stop strings
+
+        A stop string can be generated through several tokenizations (e.g. "ab" as one token
+        or as "a" + "b"), so all of them are listed, fewest tokens first for each stop string.
+
+        Args:
+            stop_sequences (list[str]) : The stop strings
+            max_variants (int) : Maximum number of tokenizations listed per stop string
+
+        Returns:
+            list[list[int]] : The token ID sequences, grouped by stop string in input order
+        """
+
+        trie = self._surface_trie()
+        variants = []
+        for stop in dict.fromkeys(stop_sequences):
+            # Tokens matching the stop string at each position
+            matches = []
+            for start in range(len(stop)):
+                node, found = trie.root, []
+                for end in range(start, len(stop)):
+                    node = node.get(stop[end])
+                    if node is None:
+                        break
+                    if None in node:
+                        found.extend((end + 1, token_id) for token_id in node[None])
+                matches.append(sorted(found, reverse=True))
+
+            # Positions from which the end of the stop string is reachable, so the search never dead-ends
+            reachable = [False] * len(stop) + [True]
+            for start in range(len(stop) - 1, -1, -1):
+                reachable[start] = any(reachable[end] for end, _ in matches[start])
+
+            found_variants = []
+            stack = [(0, [])] if stop and reachable[0] else []
+            while stack and len(found_variants) < max_variants:
+                position, token_ids = stack.pop()
+                if position == len(stop):
+                    # Decoding can add context-dependent spacing, so keep only exact round trips
+                    if self.decode(token_ids) == stop:
+                        found_variants.append(token_ids)
+                    continue
+                # Pushed in reverse so the longest tokens are explored first
+                for end, token_id in reversed(matches[position]):
+                    if reachable[end]:
+                        stack.append((end, token_ids + [token_id]))
+            variants.extend(sorted(found_variants, key=len))
+        return variants
+
+
+    def token_ids_containing(self, substring: str) -> list[int]:
+        """
+        Find every token whose decoded text contains a substring, e.g. to build logit-bias lists
+
+        Args:
+            substring (str) : The text to look for, as it appears in decoded output
+
+        Returns:
+            list[int] : Sorted token ids
+        """
+
+        if not substring:
+            return sorted(self.vocab)
+
+        joined, starts, token_ids = self._surface_index()
+        found = set()
+        position = joined.find(substring)
+        while position != -1:
+            found.add(token_ids[bisect.bisect_right(starts, position) - 1])
+            position = joined.find(substring, position + 1)
+        return sorted(found)
+
+
+    def nearest_tokens(self, text: str, k: int = 10, max_distance: int | None = None) -> list[tuple[int, str, int]]:
+        """
+        Find the regular tokens whose decoded text is closest to a string by edit distance
+
+        Useful to see which tokens a word missing from the vocabulary falls back near, or as
+        the basis of typo-tolerant matching. The first call builds a BK-tree over the vocabulary.
+
+        Args:
+            text (str) : The string, as it appears in decoded output (e.g. " hello")
+            k (int) : Number of tokens returned
+            max_distance (int or None) : Ignore tokens farther than this many edits
+
+        Returns:
+            list[tuple[int, str, int]] : (token id, decoded text, distance) of each token, closest first, ties by id
+        """
+
+        self._require_trained("search")
+        if self._nearest_index is None:
+            regular_ids = self._regular_token_trie().ids_with_prefix("")
+            self._nearest_index = BKTree({token_id : self.decode([token_id]) for token_id in regular_ids})
+        return [(token_id, self.decode([token_id]), distance)
+                for distance, token_id in self._nearest_index.nearest(text, k, max_distance)]
+
+
+    def token_ids_for_words(self, words: list[str]) -> dict[str, list[int]]:
+        """
+        Resolve words to every token that decodes to the whole word, e.g. "cat", " cat" or "cat\n"
+
+        Multi-token forms of a word are listed by token_ids_for_stop_sequences.
+
+        Args:
+            words (list[str]) : The words to resolve
+
+        Returns:
+            dict[str, list[int]] : The sorted token ids of each word
+        """
+
+        return {word : [token_id for token_id in self.token_ids_containing(word) if self.decode([token_id]).strip() == word.strip()]
+                for word in words}
+
+
+    def _surface_index(self) -> tuple[str, list[int], list[int]]:
+        # Decoded text of all tokens joined by NUL, with the start offset and id of each token
+        if self._surface_text_index is None:
+            pieces, starts, token_ids = [], [], []
+            position = 0
+            for token_id in sorted(self.vocab):
+                text = self.decode([token_id])
+                starts.append(position)
+                token_ids.append(token_id)
+                pieces.append(text)
+                position += len(text) + 1
+            self._surface_text_index = ("\0".join(pieces), starts, token_ids)
+        return self._surface_text_index
+
+
+    def _surface_trie(self) -> VocabTrie:
+        # Trie over the decoded text of every token, each node holding the list of ids ending there
+        if self._stop_trie is None:
+            self._stop_trie = VocabTrie()
+            for token_id in self.vocab:
+                text = self.decode([token_id])
+                if text:
+                    node = self._stop_trie.root
+                    for char in text:
+                        node = node.setdefault(char, {})
+                    node.setdefault(None, []).append(token_id)
+        return self._stop_trie
 
-        with open(bpe_merges_path, "r", encoding="utf-8") as file:
-            merges_list = json.load(file)
-            for merge in merges_list:
-                pair = tuple(merge["pair"])
-                new_id = merge["new_id"]
-                self.bpe_merges[pair] = new_id
-    
 
     @staticmethod
-    def find_freq_pair(token_ids: list[int], mode: str = "most") -> tuple[int, int] | None:
+    def find_freq_pair(token_ids: list[int], mode: FrequencyMode | str = FrequencyMode.MOST) -> tuple[int, int] | None:
+        try:
+            mode = FrequencyMode(mode)
+        except ValueError:
+            raise LucidInvalidConfig(f"Unknown mode {mode!r}. "
+                                     f"Choose one of {[member.value for member in FrequencyMode]}") from None
+        if mode == FrequencyMode.LEAST:
+            warnings.warn("find_freq_pair(mode='least') is deprecated : trim rare tokens with BPETokenizer.trim_vocab instead",
+                          DeprecationWarning, stacklevel=2)
         pairs = Counter(zip(token_ids, token_ids[1:]))
 
         if not pairs:
             return None
         
-        if mode == "most":
+        if mode == FrequencyMode.MOST:
             return max(pairs.items(), key=lambda x: x[1])[0]
-        elif mode == "least":
-            return min(pairs.items(), key=lambda x: x[1])[0]
-        else:
-            raise ValueError("Invalid mode. Choose 'most' or 'least'")
+        return min(pairs.items(), key=lambda x: x[1])[0]
         
     @staticmethod
     def replace_pair(token_ids: list[int], pair_id: tuple[int, int], new_id: int) -> list[int]:
@@ -0,0 +1,69 @@
+class VocabTrie:
+
+    def __init__(self, vocab: dict[int, str] | None = None):
+        # Nested dicts : {char: child_node}, the None key holds the token id ending at that node
+        self.root = {}
+        if vocab:
+            for token_id, token in vocab.items():
+                self.insert(token, token_id)
+
+
+    def insert(self, token: str, token_id: int) -> None:
+        """
+        Insert a token into the trie
+
+        Args:
+            token (str) : The token string
+            token_id (int) : The token id
+        """
+
+        node = self.root
+        for char in token:
+            node = node.setdefault(char, {})
+        node[None] = token_id
+
+
+    def find_node(self, prefix: str) -> dict | None:
+        """
+        Walk the trie along a prefix
+
+        Args:
+            prefix (str) : The prefix to follow
+
+        Returns:
+            dict or None : The node reached, None if no token starts with the prefix
+        """
+
+        node = self.root
+        for char in prefix:
+            node = node.get(char)
+            if node is None:
+                return None
+        return node
+
+
+    def ids_with_prefix(self, prefix: str) -> list[int]:
+        """
+        Collect the ids of all tokens starting with a prefix
+
+        Args:
+            prefix (str) : The prefix to search
+
+        Returns:
+            list[int] : Sorted token ids
+        """
+
+        node = self.find_node(prefix)
+        if node is None:
+            return []
+
+        ids = []
+        stack = [node]
+        while stack:
+            current = stack.pop()
+            for key, child in current.items():
+                if key is None:
+                    ids.append(child)
+                else:
+                    stack.append(child)
+        return sorted(ids)
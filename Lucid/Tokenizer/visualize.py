@@ -0,0 +1,65 @@
+import html
+
+from .config import VisualizationFormat
+
+# Background colors cycled over consecutive tokens
+HTML_COLORS = ("#fde2e4", "#dfe7fd", "#e2f0cb", "#fff1c1", "#e8dff5")
+# ANSI background color codes cycled over consecutive tokens (red, green, yellow, blue, magenta, cyan)
+ANSI_COLORS = (41, 42, 43, 44, 45, 46)
+ANSI_RESET = "\x1b[0m"
+ANSI_DIM = "\x1b[2m"
+
+
+class HtmlVisualization(str):
+    # An HTML string that notebooks (Jupyter, VS Code) render instead of printing
+
+    def _repr_html_(self) -> str:
+        return str(self)
+
+
+def _segments(spans: list[tuple[int, str, int, int]]) -> list[tuple[int, int, list[tuple[int, str]]]]:
+    # Group tokens covering overlapping text (byte-level tokens splitting a character) into one segment
+    segments = []
+    for token_id, token, start, end in spans:
+        if segments and start < segments[-1][1]:
+            previous_start, previous_end, tokens = segments[-1]
+            segments[-1] = (previous_start, max(previous_end, end), tokens + [(token_id, token)])
+        else:
+            segments.append((start, end, [(token_id, token)]))
+    return segments
+
+
+def render_tokens(text: str, spans: list[tuple[int, str, int, int]], format: VisualizationFormat | str) -> str:
+    """
+    Render a text with every token on its own background color
+
+    Tokens that cover no text (e.g. case markers) are shown as their vocabulary entry, dimmed.
+
+    Args:
+        text (str) : The encoded text
+        spans (list[tuple[int, str, int, int]]) : (token id, vocabulary entry, start, end) of each token,
+            with character offsets into the text
+        format (VisualizationFormat or str) : "html" or "ansi"
+
+    Returns:
+        str : The rendering, an HtmlVisualization for the "html" format
+    """
+
+    format = VisualizationFormat(format)
+    parts = []
+    for index, (start, end, tokens) in enumerate(_segments(spans)):
+        label = ", ".join(f"{token_id} {token!r}" for token_id, token in tokens)
+        surface = text[start:end] if end > start else "".join(token for _, token in tokens)
+        if format == VisualizationFormat.HTML:
+            style = f"background-color: {HTML_COLORS[index % len(HTML_COLORS)]}"
+            if end == start:
+                style += "; opacity: 0.5; font-size: 0.8em"
+            parts.append(f'<span style="{style}" title="{html.escape(label)}">{html.escape(surface)}</span>')
+        else:
+            color = f"\x1b[30;{ANSI_COLORS[index % len(ANSI_COLORS)]}m" + (ANSI_DIM if end == start else "")
+            # Close the color before every newline so it does not fill the rest of the terminal line
+            parts.append(color + surface.replace("\n", f"{ANSI_RESET}\n{color}") + ANSI_RESET)
+
+    if format == VisualizationFormat.HTML:
+        return HtmlVisualization('<pre style="white-space: pre-wrap; line-height: 1.6">' + "".join(parts) + "</pre>")
+    return "".join(parts)
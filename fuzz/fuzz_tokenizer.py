@@ -0,0 +1,43 @@
+"""
+Fuzz target for encode/decode/load, run with atheris :
+
+    pip install atheris
+    python fuzz/fuzz_tokenizer.py
+"""
+
+import sys
+
+import atheris
+
+with atheris.instrument_imports():
+    from Lucid.Tokenizer import BPETokenizer, PreTokenizer, TokenizerConfig
+
+CORPUS = "def main():\n    return 0\n\nThe quick brown fox jumps over the lazy dog. 0123456789\n"
+
+tokenizer = BPETokenizer(TokenizerConfig(pre_tokenizer=PreTokenizer.CODE))
+tokenizer.train(CORPUS, 400)
+tokenizer.set_special_token("unk", "<unk>")
+
+
+def test_one_input(data: bytes) -> None:
+    provider = atheris.FuzzedDataProvider(data)
+    choice = provider.ConsumeIntInRange(0, 2)
+
+    if choice == 0:
+        # Lossless round trip for text made of known characters
+        text = "".join(char for char in provider.ConsumeUnicodeNoSurrogates(256) if char in tokenizer.inverse_vocab and char != "Ġ")
+        assert tokenizer.decode(tokenizer.encode(text)) == text
+    elif choice == 1:
+        # Arbitrary text must encode (unknown characters map to <unk>) and decode
+        tokenizer.decode(tokenizer.encode(provider.ConsumeUnicodeNoSurrogates(256)))
+    else:
+        # Malformed artifacts must fail with an exception, never crash
+        try:
+            BPETokenizer.from_json_str(provider.ConsumeUnicodeNoSurrogates(1024))
+        except (ValueError, KeyError, TypeError, AttributeError):
+            pass
+
+
+if __name__ == "__main__":
+    atheris.Setup(sys.argv, test_one_input)
+    atheris.Fuzz()
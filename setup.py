@@ -9,7 +9,14 @@ setup(
     long_description=open('README.md').read(),
     long_description_content_type='text/markdown',
     packages=find_packages(),
+    package_data={"Lucid": ["py.typed"]},
     install_requires=[],
+    extras_require={
+        "arrow": ["pyarrow"],
+        "numpy": ["numpy"],
+        "hf-hub": ["huggingface_hub"],
+        "zstd": ["zstandard"],
+    },
     python_requires=">=3.9",
     url="https://github.com/KushalGajjar1/Lucid",
     classifiers=[
@@ -17,5 +24,8 @@ setup(
         "License :: OSI Approved :: MIT License",
         "Operating System :: OS Independent",
     ],
-    include_package_data=True
+    include_package_data=True,
+    entry_points={
+        "console_scripts": ["lucid=Lucid.Tokenizer.cli:main"],
+    },
 )
\ No newline at end of file
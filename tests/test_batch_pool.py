@@ -0,0 +1,55 @@
+from concurrent.futures import ThreadPoolExecutor
+import unittest
+from unittest import mock
+
+from Lucid.Tokenizer import BPETokenizer
+from Lucid.Tokenizer import tokenizer as tokenizer_module
+
+# Large enough (in characters and token ids) to take the parallel batch paths
+TEXTS = [" ".join(["the quick brown fox jumps over the lazy dog"] * 150)] * 200
+
+
+class BatchPoolTest(unittest.TestCase):
+
+    def setUp(self):
+        self.tokenizer = BPETokenizer()
+        self.tokenizer.train("the quick brown fox jumps over the lazy dog\n" * 20, 300)
+        self.addCleanup(self.tokenizer._shutdown_pool)
+
+
+    def test_default_batch_starts_no_process_pool(self):
+        # Process pools are opt-in, e.g. for scripts without a main-module guard or server threads
+        with mock.patch.object(tokenizer_module, "ProcessPoolExecutor", side_effect=AssertionError("process pool started")):
+            sequences = self.tokenizer.encode_batch(TEXTS)
+            texts = self.tokenizer.decode_batch(sequences)
+        self.assertEqual(sequences[0], self.tokenizer.encode(TEXTS[0]))
+        self.assertEqual(texts[0], self.tokenizer.decode(sequences[0]))
+
+
+    def test_pool_reused_across_batches(self):
+        with mock.patch.object(tokenizer_module, "ProcessPoolExecutor", wraps=tokenizer_module.ProcessPoolExecutor) as pool:
+            sequences = self.tokenizer.encode_batch(TEXTS, num_workers=2)
+            self.assertEqual(self.tokenizer.encode_batch(TEXTS, num_workers=2), sequences)
+            self.assertEqual(self.tokenizer.decode_batch(sequences, num_workers=2), self.tokenizer.decode_batch(sequences))
+        self.assertEqual(pool.call_count, 1)
+        self.assertEqual(sequences[0], self.tokenizer.encode(TEXTS[0]))
+
+
+    def test_concurrent_batches_share_one_pool(self):
+        with mock.patch.object(tokenizer_module, "ProcessPoolExecutor", wraps=tokenizer_module.ProcessPoolExecutor) as pool:
+            with ThreadPoolExecutor(max_workers=4) as threads:
+                results = list(threads.map(lambda _: self.tokenizer.encode_batch(TEXTS, num_workers=2), range(4)))
+        self.assertEqual(pool.call_count, 1)
+        self.assertTrue(all(result == results[0] for result in results))
+
+
+    def test_pool_restarted_after_change(self):
+        self.tokenizer.encode_batch(TEXTS, num_workers=2)
+        fox_id = self.tokenizer.add_special_tokens(["<|fox|>"])[0]
+        self.assertIsNone(self.tokenizer._pool)
+        sequences = self.tokenizer.encode_batch([text + "<|fox|>" for text in TEXTS], allowed_special="all", num_workers=2)
+        self.assertEqual(sequences[0][-1], fox_id)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,89 @@
+import unittest
+
+from Lucid.Tokenizer import (BlankInputPolicy, BPETokenizer, LucidBlankInput, LucidInvalidConfig, PreTokenizer, TokenizerConfig,
+                             WordBoundary)
+
+# Tokenizer settings whose blank input handling is checked
+CONFIGS = {
+    "whitespace" : {},
+    "code" : {"pre_tokenizer" : PreTokenizer.CODE},
+    "byte_level" : {"byte_level" : True},
+    "end_of_word" : {"word_boundary" : WordBoundary.END, "space_marker" : None},
+}
+CORPUS = "\n".join(["the quick brown fox jumps over the lazy dog"] * 20)
+BLANK_TEXTS = [" ", "   ", "\t", "\n", "\n\n", " \n \n", "　"]
+
+
+def trained_tokenizer(policy: BlankInputPolicy, **settings) -> BPETokenizer:
+    tokenizer = BPETokenizer(TokenizerConfig(blank_input=policy, **settings))
+    tokenizer.train(CORPUS, 280)
+    return tokenizer
+
+
+class BlankInputTest(unittest.TestCase):
+
+    def test_empty_text_encodes_to_nothing(self):
+        for name, settings in CONFIGS.items():
+            for policy in (BlankInputPolicy.ENCODE, BlankInputPolicy.EMPTY, BlankInputPolicy.SPACE):
+                with self.subTest(name=name, policy=policy):
+                    tokenizer = trained_tokenizer(policy, **settings)
+                    self.assertEqual(tokenizer.encode(""), [])
+                    self.assertEqual(tokenizer.decode([]), "")
+
+
+    def test_empty_policy(self):
+        for name, settings in CONFIGS.items():
+            tokenizer = trained_tokenizer(BlankInputPolicy.EMPTY, **settings)
+            for text in BLANK_TEXTS:
+                with self.subTest(name=name, text=text):
+                    self.assertEqual(tokenizer.encode(text), [])
+
+
+    def test_space_policy(self):
+        for name, settings in CONFIGS.items():
+            tokenizer = trained_tokenizer(BlankInputPolicy.SPACE, **settings)
+            for text in BLANK_TEXTS:
+                with self.subTest(name=name, text=text):
+                    token_ids = tokenizer.encode(text)
+                    self.assertEqual(len(token_ids), 1)
+                    self.assertEqual(tokenizer.decode(token_ids), " ")
+                    self.assertEqual(tokenizer.encode_plus(text).offsets[0][0], 0)
+
+
+    def test_space_policy_without_space_token(self):
+        # End-of-word marking has no standalone space marker token
+        with self.assertRaises(LucidInvalidConfig):
+            TokenizerConfig(word_boundary=WordBoundary.END, blank_input=BlankInputPolicy.SPACE).validate()
+
+        # A vocabulary built without the marker token is reported instead of raising KeyError
+        tokenizer = BPETokenizer.from_vocab_and_merges({"a" : 0, "b" : 1}, [], TokenizerConfig(blank_input=BlankInputPolicy.SPACE))
+        with self.assertRaises(LucidInvalidConfig):
+            tokenizer.encode("   ")
+
+
+    def test_error_policy(self):
+        tokenizer = trained_tokenizer(BlankInputPolicy.ERROR)
+        for text in [""] + BLANK_TEXTS:
+            with self.subTest(text=text), self.assertRaises(LucidBlankInput):
+                tokenizer.encode(text)
+        # Text with any visible character is encoded normally
+        self.assertEqual(tokenizer.decode(tokenizer.encode(" the ")), "the")
+
+
+    def test_newline_runs_decode_exactly(self):
+        for name, settings in CONFIGS.items():
+            tokenizer = trained_tokenizer(BlankInputPolicy.ENCODE, **settings)
+            for text in ("\n", "\n\n", "\n\n\n"):
+                with self.subTest(name=name, text=text):
+                    self.assertEqual(tokenizer.decode(tokenizer.encode(text)), text)
+
+
+    def test_policy_is_saved(self):
+        tokenizer = trained_tokenizer(BlankInputPolicy.SPACE)
+        restored = BPETokenizer.from_json_str(tokenizer.to_json_str())
+        self.assertEqual(restored.config.blank_input, BlankInputPolicy.SPACE)
+        self.assertNotEqual(restored.fingerprint(), trained_tokenizer(BlankInputPolicy.ENCODE).fingerprint())
+
+
+if __name__ == "__main__":
+    unittest.main()
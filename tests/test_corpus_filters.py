@@ -0,0 +1,53 @@
+from dataclasses import asdict
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, LanguageFilter, LengthFilter, LucidInvalidConfig, PrintableFilter, TrainerConfig
+from Lucid.Tokenizer.corpus import accepts_line, filter_text, guess_language
+
+
+class CorpusFilterTest(unittest.TestCase):
+
+    def test_length_filter(self):
+        corpus_filter = LengthFilter(min_chars=5, max_chars=10)
+        lines = ["abcd", "  abcde  ", "abcdefghijk"]
+        self.assertEqual([accepts_line(line, corpus_filter) for line in lines], [False, True, False])
+
+
+    def test_printable_filter(self):
+        corpus_filter = PrintableFilter(min_ratio=0.9)
+        self.assertTrue(accepts_line("plain text\twith a tab", corpus_filter))
+        self.assertFalse(accepts_line("bin\x00\x01\x02\x03ary", corpus_filter))
+
+
+    def test_language_filter(self):
+        self.assertEqual(guess_language("the cat is in the garden"), "en")
+        self.assertEqual(guess_language("le chat est dans la maison"), "fr")
+        self.assertEqual(guess_language("Привет, мир"), "ru")
+        self.assertIsNone(guess_language("12345"))
+        self.assertFalse(accepts_line("12345", LanguageFilter(["en"])))
+        self.assertTrue(accepts_line("12345", LanguageFilter(["en"], keep_unknown=True)))
+
+
+    def test_filter_text_keeps_blank_lines(self):
+        text = "the cat is in the garden\n\nle chat est dans la maison\nthe dog and the cat"
+        filtered, skipped = filter_text(text, [LanguageFilter(["en"]), LengthFilter(min_chars=3)])
+        self.assertEqual((filtered, skipped), ("the cat is in the garden\n\nthe dog and the cat", 1))
+
+
+    def test_training_reports_filtered_lines(self):
+        config = TrainerConfig(filters=[LanguageFilter(["en"])])
+        tokenizer = BPETokenizer()
+        stats = tokenizer.train("\n".join(["the cat is in the garden", "le chat est dans la maison"] * 10), 280, config=config)
+        self.assertEqual(stats.lines_filtered, 10)
+        self.assertNotIn("Ġmaison", tokenizer.inverse_vocab)
+
+
+    def test_filters_restored_from_dicts(self):
+        config = TrainerConfig(filters=[asdict(LengthFilter(min_chars=2)), asdict(PrintableFilter())])
+        self.assertEqual(config.filters, [LengthFilter(min_chars=2), PrintableFilter()])
+        with self.assertRaises(LucidInvalidConfig):
+            TrainerConfig(filters=[{"kind" : "profanity"}])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,43 @@
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, LucidFingerprintMismatch, TokenizerConfig
+from Lucid.Tokenizer.embedded import unpack_artifact
+
+CORPUS = "\n".join(["the café serves crème brûlée", "hello world"] * 20)
+
+
+class EmbeddedArtifactTest(unittest.TestCase):
+
+    def setUp(self):
+        self.tokenizer = BPETokenizer(TokenizerConfig(byte_level=True))
+        self.tokenizer.train(CORPUS, 300)
+        self.data = self.tokenizer.to_embedded()
+
+
+    def test_roundtrip(self):
+        restored = BPETokenizer.from_embedded(self.data, expected_fingerprint=self.tokenizer.fingerprint())
+        self.assertEqual(restored, self.tokenizer)
+        self.assertEqual(restored.encode("the café"), self.tokenizer.encode("the café"))
+
+
+    def test_truncated_artifact(self):
+        # Every cut, including ones inside the uint32 arrays, is reported as a ValueError
+        for length in [27, 30, 41] + list(range(0, len(self.data), 97)) + [len(self.data) - 1]:
+            with self.subTest(length=length), self.assertRaises(ValueError):
+                unpack_artifact(self.data[:length])
+        with self.assertRaises(ValueError):
+            unpack_artifact(self.data + b"\0")
+
+
+    def test_bad_magic(self):
+        with self.assertRaisesRegex(ValueError, "bad magic"):
+            unpack_artifact(b"NOPE" + self.data[4:])
+
+
+    def test_fingerprint_mismatch(self):
+        with self.assertRaises(LucidFingerprintMismatch):
+            BPETokenizer.from_embedded(self.data, expected_fingerprint="sha256:" + "0" * 64)
+
+
+if __name__ == "__main__":
+    unittest.main()
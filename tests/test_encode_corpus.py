@@ -0,0 +1,46 @@
+import io
+import json
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, CorpusFormat
+from Lucid.Tokenizer import parallel
+from Lucid.Tokenizer import tokenizer as tokenizer_module
+
+LINES = ["the quick brown fox", "", "jumps over the lazy dog"] * 50
+
+
+class EncodeCorpusTest(unittest.TestCase):
+
+    def setUp(self):
+        self.tokenizer = BPETokenizer()
+        self.tokenizer.train("\n".join(LINES), 280)
+
+
+    def encode_corpus(self, num_threads: int) -> bytes:
+        parallel.set_num_threads(num_threads)
+        self.addCleanup(parallel.set_num_threads, None)
+        writer = io.BytesIO()
+        stats = self.tokenizer.encode_corpus(io.StringIO("\n".join(LINES) + "\n"), writer, CorpusFormat.JSONL, chunk_lines=16)
+        self.assertEqual(stats.num_documents, len(LINES))
+        return writer.getvalue()
+
+
+    def test_in_process_leaves_no_worker_tokenizer(self):
+        output = self.encode_corpus(1)
+        self.assertEqual([json.loads(line) for line in output.splitlines()], [self.tokenizer.encode(line) for line in LINES])
+        self.assertIsNone(tokenizer_module._WORKER_TOKENIZER)
+
+
+    def test_workers_match_in_process(self):
+        self.assertEqual(self.encode_corpus(2), self.encode_corpus(1))
+
+
+    def test_imap_ordered_skips_initializer_in_process(self):
+        calls = []
+        results = list(parallel.imap_ordered(abs, [-1, 2, -3], num_workers=1, initializer=calls.append, initargs=("init",)))
+        self.assertEqual(results, [1, 2, 3])
+        self.assertEqual(calls, [])
+
+
+if __name__ == "__main__":
+    unittest.main()
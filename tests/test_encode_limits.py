@@ -0,0 +1,55 @@
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, EncodeLimits, LucidEncodeLimitExceeded
+
+CORPUS = "\n".join(["hello world <|endoftext|> héllo wörld"] * 20)
+
+
+def trained_tokenizer() -> BPETokenizer:
+    tokenizer = BPETokenizer()
+    tokenizer.train(CORPUS, 300, {"<|endoftext|>"})
+    return tokenizer
+
+
+class EncodeLimitsTest(unittest.TestCase):
+
+    def setUp(self):
+        self.tokenizer = trained_tokenizer()
+
+
+    def test_max_input_bytes(self):
+        self.tokenizer.set_encode_limits(EncodeLimits(max_input_bytes=10))
+        self.assertTrue(self.tokenizer.encode("hello wor"))
+        # 9 characters, but 11 UTF-8 bytes
+        with self.assertRaises(LucidEncodeLimitExceeded) as context:
+            self.tokenizer.encode("héllo wör")
+        error = context.exception
+        self.assertEqual((error.limit, error.maximum, error.actual), ("max_input_bytes", 10, 11))
+
+
+    def test_max_output_tokens_stops_early(self):
+        self.tokenizer.set_encode_limits(EncodeLimits(max_output_tokens=3))
+        with self.assertRaises(LucidEncodeLimitExceeded) as context:
+            self.tokenizer.encode("hello world " * 2000)
+        # Encoding stops at the first pre-token past the limit rather than after the whole text
+        self.assertEqual(context.exception.limit, "max_output_tokens")
+        self.assertLessEqual(context.exception.actual, 3 + max(len(self.tokenizer.encode(word)) for word in ("hello", " world")))
+
+
+    def test_max_output_tokens_with_special_tokens(self):
+        text = "hello<|endoftext|>hello world hello world"
+        num_tokens = len(self.tokenizer.encode(text, "all"))
+        self.tokenizer.set_encode_limits(EncodeLimits(max_output_tokens=num_tokens))
+        self.assertEqual(len(self.tokenizer.encode(text, "all")), num_tokens)
+        self.tokenizer.set_encode_limits(EncodeLimits(max_output_tokens=num_tokens - 1))
+        with self.assertRaises(LucidEncodeLimitExceeded):
+            self.tokenizer.encode(text, "all")
+
+
+    def test_no_limits(self):
+        self.tokenizer.set_encode_limits(None)
+        self.assertGreater(len(self.tokenizer.encode("hello world " * 2000)), 3)
+
+
+if __name__ == "__main__":
+    unittest.main()
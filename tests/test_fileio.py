@@ -0,0 +1,55 @@
+import os
+import tempfile
+import threading
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer
+from Lucid.Tokenizer.fileio import LOCK_SUFFIX, open_text
+
+CORPUS = "\n".join(["the quick brown fox jumps over the lazy dog"] * 20)
+
+
+class AtomicWriteTest(unittest.TestCase):
+
+    def setUp(self):
+        directory = tempfile.TemporaryDirectory()
+        self.addCleanup(directory.cleanup)
+        self.directory = directory.name
+        self.path = os.path.join(self.directory, "tokenizer.json")
+
+
+    def test_failed_write_keeps_previous_file(self):
+        with open_text(self.path, "w", atomic=True) as file:
+            file.write("previous")
+        with self.assertRaises(RuntimeError):
+            with open_text(self.path, "w", atomic=True) as file:
+                file.write("partial")
+                raise RuntimeError("interrupted")
+        with open_text(self.path, "r") as file:
+            self.assertEqual(file.read(), "previous")
+        # The temporary file is removed
+        self.assertEqual(os.listdir(self.directory), ["tokenizer.json"])
+
+
+    def test_concurrent_locked_saves(self):
+        tokenizers = []
+        for vocab_size in (270, 280, 290):
+            tokenizer = BPETokenizer()
+            tokenizer.train(CORPUS, vocab_size)
+            tokenizers.append(tokenizer)
+
+        threads = [threading.Thread(target=tokenizer.save, args=(self.path,), kwargs={"lock" : True})
+                   for tokenizer in tokenizers * 3]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+
+        # Every save completed in turn : the artifact is one of them, whole
+        self.assertIn(BPETokenizer.load(self.path), tokenizers)
+        self.assertTrue(os.path.exists(self.path + LOCK_SUFFIX))
+        self.assertEqual(sorted(os.listdir(self.directory)), ["tokenizer.json", "tokenizer.json" + LOCK_SUFFIX])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,24 @@
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, FrequencyMode, LucidInvalidConfig
+
+TOKEN_IDS = [1, 2, 1, 2, 3, 4]
+
+
+class FrequencyModeTest(unittest.TestCase):
+
+    def test_modes(self):
+        self.assertEqual(BPETokenizer.find_freq_pair(TOKEN_IDS), (1, 2))
+        self.assertEqual(BPETokenizer.find_freq_pair(TOKEN_IDS, "most"), (1, 2))
+        with self.assertWarns(DeprecationWarning):
+            self.assertEqual(BPETokenizer.find_freq_pair(TOKEN_IDS, FrequencyMode.LEAST), (2, 1))
+        self.assertIsNone(BPETokenizer.find_freq_pair([1]))
+
+
+    def test_unknown_mode(self):
+        with self.assertRaisesRegex(LucidInvalidConfig, "most"):
+            BPETokenizer.find_freq_pair(TOKEN_IDS, "rarest")
+
+
+if __name__ == "__main__":
+    unittest.main()
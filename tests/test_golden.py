@@ -0,0 +1,84 @@
+from pathlib import Path
+import sys
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, PreTokenizer, TokenizerConfig, WordBoundary
+
+# Directory holding <name>.tokenizer.json artifacts and their <name>.json reference cases
+GOLDEN_DIR = Path(__file__).parent / "golden"
+
+# Settings of every golden tokenizer
+CONFIGS = {
+    "whitespace" : TokenizerConfig(),
+    "code" : TokenizerConfig(pre_tokenizer=PreTokenizer.CODE, split_digits=True),
+    "byte_level" : TokenizerConfig(byte_level=True),
+    "end_of_word" : TokenizerConfig(word_boundary=WordBoundary.END, space_marker=None),
+    "case_markers" : TokenizerConfig(case_markers=True),
+}
+
+# Corpus the golden tokenizers were trained on (only used when regenerating)
+CORPUS = "\n".join([
+    "The quick brown fox jumps over the lazy dog.",
+    "Tokenizers split text into subword units; BPE merges the most frequent pairs.",
+    "def encode(self, text):\n    return [token_id for token_id in self.tokenize(text)]",
+    "for i in range(10):\n\tprint(i * 2, 3.14159, 0x1F)",
+    "Les élèves étudient à l'école. Über den Wolken. ¿Dónde está?",
+    "東京は日本の首都です。한국어 텍스트. Ελληνικά. Привет, мир!",
+    "Emoji 😀👍 and symbols © ® ™ → ← ≠ ≤ ≥",
+    "UPPER CASE WORDS and Capitalized Words and mixed iPhone words",
+] * 8)
+
+# Texts whose encodings are pinned
+TEXTS = [
+    "The quick brown fox jumps over the lazy dog.",
+    "tokenizers merge frequent pairs",
+    "def encode(self, text):\n    return text",
+    "print(12345 + 678)",
+    "Les élèves étudient à l'école.",
+    "Привет, мир!",
+    "Emoji 😀 and symbols ©",
+    "UPPER CASE and Capitalized",
+    "the<|endoftext|>quick fox",
+    "",
+]
+
+
+def regenerate() -> None:
+    # Retrain the golden tokenizers and record their current encodings
+    GOLDEN_DIR.mkdir(exist_ok=True)
+    for name, config in CONFIGS.items():
+        tokenizer = BPETokenizer(config)
+        tokenizer.train(CORPUS, 400)
+        tokenizer.save(GOLDEN_DIR / f"{name}.tokenizer.json")
+        tokenizer.record_reference(TEXTS, GOLDEN_DIR / f"{name}.json", allowed_special={"<|endoftext|>"})
+
+
+class GoldenTest(unittest.TestCase):
+
+    def test_golden_encodings(self):
+        for name in CONFIGS:
+            with self.subTest(name=name):
+                tokenizer = BPETokenizer.load(GOLDEN_DIR / f"{name}.tokenizer.json")
+                report = tokenizer.verify_against_reference(GOLDEN_DIR / f"{name}.json")
+                self.assertTrue(report.fingerprint_matches)
+                self.assertEqual(report.num_cases, len(TEXTS))
+                self.assertTrue(report.ok, report.mismatches)
+
+
+    def test_detects_changed_encoding(self):
+        tokenizer = BPETokenizer.load(GOLDEN_DIR / "whitespace.tokenizer.json")
+        # Dropping the merges changes most encodings and the fingerprint
+        tokenizer.bpe_merges = {}
+        tokenizer._invalidate_caches()
+        report = tokenizer.verify_against_reference(GOLDEN_DIR / "whitespace.json")
+        self.assertFalse(report.fingerprint_matches)
+        self.assertFalse(report.ok)
+        self.assertEqual(report.mismatches[0].index, 0)
+
+
+if __name__ == "__main__":
+    # python -m tests.test_golden --regenerate rewrites the golden files after an intended change
+    if "--regenerate" in sys.argv:
+        regenerate()
+    else:
+        unittest.main()
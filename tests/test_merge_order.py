@@ -0,0 +1,29 @@
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer
+
+# "bc" is learned before "ab", and "abc" only as "a" + "bc"
+CORPUS = "\n".join(["bc"] * 6 + ["ab"] * 3 + ["xabcx"])
+
+
+class MergeOrderTest(unittest.TestCase):
+
+    def setUp(self):
+        self.tokenizer = BPETokenizer()
+        self.tokenizer.train(CORPUS, 264)
+
+
+    def test_lowest_rank_pair_merged_first(self):
+        # Merging "ab" first, left to right, would leave "ab" + "c" with no merge between them
+        token_ids = self.tokenizer.encode("abc")
+        self.assertEqual([self.tokenizer.vocab[token_id] for token_id in token_ids], ["abc"])
+
+
+    def test_trace_follows_rank_order(self):
+        trace = self.tokenizer.bpe_trace("abc")
+        self.assertEqual([(self.tokenizer.vocab[step.new_id], step.merge_pass) for step in trace], [("bc", 0), ("abc", 1)])
+        self.assertEqual([step.rank for step in trace], sorted(step.rank for step in trace))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,72 @@
+import json
+import os
+import tempfile
+import unittest
+
+from Lucid.Tokenizer import (BPETokenizer, LucidInvalidMerge, PreTokenizer, TokenizerConfig, WordBoundary, infer_config,
+                             migrate_artifact)
+
+CORPUS = "\n".join([
+    "The café serves crème brûlée",
+    "def encode(text):\n    return text",
+    "東京は日本の首都です。Привет, мир!",
+] * 20)
+
+# Settings inferred back from the tokens of a tokenizer trained with them
+CONFIGS = {
+    "whitespace" : TokenizerConfig(),
+    "sentencepiece_marker" : TokenizerConfig(space_marker="▁"),
+    "code" : TokenizerConfig(pre_tokenizer=PreTokenizer.CODE),
+    "byte_level" : TokenizerConfig(byte_level=True),
+    "end_of_word" : TokenizerConfig(word_boundary=WordBoundary.END, space_marker=None),
+    "case_markers" : TokenizerConfig(case_markers=True),
+}
+
+
+class MigrateTest(unittest.TestCase):
+
+    def setUp(self):
+        directory = tempfile.TemporaryDirectory()
+        self.addCleanup(directory.cleanup)
+        self.vocab_path = os.path.join(directory.name, "vocab.json")
+        self.merges_path = os.path.join(directory.name, "merges.json")
+        self.out_path = os.path.join(directory.name, "tokenizer.json")
+
+
+    def test_infer_config(self):
+        for name, config in CONFIGS.items():
+            with self.subTest(name=name):
+                tokenizer = BPETokenizer(config)
+                tokenizer.train(CORPUS, 360)
+                self.assertEqual(infer_config(tokenizer.vocab, tokenizer.bpe_merges), config)
+
+
+    def test_latin1_text_is_not_byte_level(self):
+        # Plain tokens such as "café" read as bytes end with a cut UTF-8 sequence
+        tokenizer = BPETokenizer()
+        tokenizer.train("\n".join(["naïve café", "crème brûlée"] * 20), 300)
+        self.assertFalse(infer_config(tokenizer.vocab, tokenizer.bpe_merges).byte_level)
+
+
+    def test_migrate_artifact(self):
+        tokenizer = BPETokenizer(TokenizerConfig(byte_level=True))
+        tokenizer.train(CORPUS, 360)
+        tokenizer.save_vocab_and_merges(self.vocab_path, self.merges_path)
+
+        migrated = migrate_artifact(self.vocab_path, self.merges_path, self.out_path)
+        self.assertEqual(migrated.config, tokenizer.config)
+        self.assertEqual(BPETokenizer.load(self.out_path).encode(CORPUS), tokenizer.encode(CORPUS))
+
+
+    def test_merge_outside_vocabulary(self):
+        with open(self.vocab_path, "w", encoding="utf-8") as file:
+            json.dump({"0" : "a", "1" : "b"}, file)
+        with open(self.merges_path, "w", encoding="utf-8") as file:
+            json.dump([{"pair" : [0, 1], "new_id" : 2}], file)
+        with self.assertRaises(LucidInvalidMerge):
+            migrate_artifact(self.vocab_path, self.merges_path, self.out_path)
+        self.assertFalse(os.path.exists(self.out_path))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,90 @@
+import random
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, PreTokenizer, TokenizerConfig
+
+# Characters drawn when generating arbitrary strings
+ALPHABET = (
+    "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789"
+    "_.,;:!?()[]{}<>=+-*/\\|'\"#@$%^&~`"
+    "     \t\t\n\n\r"
+    "éüßÆøñ漢字かな한글Ωπ😀👍́  　"
+)
+NUM_CASES = 200
+SEEDS = range(5)
+
+
+def random_strings(seed: int, marker: str) -> list[str]:
+    rng = random.Random(seed)
+    alphabet = [char for char in ALPHABET if char != marker]
+    return ["".join(rng.choice(alphabet) for _ in range(rng.randint(0, 40))) for _ in range(NUM_CASES)]
+
+
+def trained_tokenizer(config: TokenizerConfig, texts: list[str]) -> BPETokenizer:
+    tokenizer = BPETokenizer(config)
+    tokenizer.train("\n".join(texts), 400)
+    return tokenizer
+
+
+class CodeRoundTripTest(unittest.TestCase):
+
+    def check_lossless(self, config: TokenizerConfig) -> None:
+        for seed in SEEDS:
+            texts = random_strings(seed, config.space_marker)
+            tokenizer = trained_tokenizer(config, texts)
+            for text in texts:
+                with self.subTest(seed=seed, text=text):
+                    self.assertEqual(tokenizer.decode(tokenizer.encode(text)), text)
+
+
+    def test_code_roundtrip(self):
+        self.check_lossless(TokenizerConfig(pre_tokenizer=PreTokenizer.CODE))
+
+
+    def test_code_roundtrip_split_digits(self):
+        self.check_lossless(TokenizerConfig(pre_tokenizer=PreTokenizer.CODE, split_digits=True))
+
+
+    def test_code_roundtrip_sentencepiece_marker(self):
+        self.check_lossless(TokenizerConfig(pre_tokenizer=PreTokenizer.CODE, space_marker="▁"))
+
+
+    def test_code_roundtrip_literal_spaces(self):
+        self.check_lossless(TokenizerConfig(pre_tokenizer=PreTokenizer.CODE, space_marker=None))
+
+
+    def test_code_offsets_cover_text(self):
+        config = TokenizerConfig(pre_tokenizer=PreTokenizer.CODE)
+        texts = random_strings(0, config.space_marker)
+        tokenizer = trained_tokenizer(config, texts)
+        for text in texts:
+            with self.subTest(text=text):
+                offsets = tokenizer.encode_plus(text).offsets
+                self.assertEqual("".join(text[start:end] for start, end in offsets), text)
+
+
+class WhitespaceRoundTripTest(unittest.TestCase):
+
+    def test_single_line_normalizes_whitespace(self):
+        config = TokenizerConfig()
+        for seed in SEEDS:
+            texts = [text.replace("\n", " ") for text in random_strings(seed, config.space_marker)]
+            tokenizer = trained_tokenizer(config, texts)
+            for text in texts:
+                with self.subTest(seed=seed, text=text):
+                    # Runs of whitespace collapse to a single space and leading/trailing whitespace is dropped
+                    self.assertEqual(tokenizer.decode(tokenizer.encode(text)), " ".join(text.split()))
+
+
+    def test_serialization_preserves_encoding(self):
+        config = TokenizerConfig()
+        texts = random_strings(0, config.space_marker)
+        tokenizer = trained_tokenizer(config, texts)
+        loaded = BPETokenizer.from_json_str(tokenizer.to_json_str())
+        for text in texts:
+            with self.subTest(text=text):
+                self.assertEqual(loaded.encode(text), tokenizer.encode(text))
+
+
+if __name__ == "__main__":
+    unittest.main()
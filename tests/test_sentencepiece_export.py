@@ -0,0 +1,28 @@
+import io
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, TokenizerConfig
+
+CORPUS = "hello world\nthe quick brown fox\n" * 10
+
+
+class SentencePieceExportTest(unittest.TestCase):
+
+    def test_space_marker_exported(self):
+        tokenizer = BPETokenizer(TokenizerConfig(space_marker="▁"))
+        tokenizer.train(CORPUS, 280)
+        file = io.BytesIO()
+        tokenizer.export_sentencepiece(file)
+        self.assertIn("▁quick".encode("utf-8"), file.getvalue())
+
+
+    def test_literal_spaces_rejected(self):
+        # Without a space marker the pieces cannot carry SentencePiece's "▁"
+        tokenizer = BPETokenizer(TokenizerConfig(space_marker=None))
+        tokenizer.train(CORPUS, 280)
+        with self.assertRaises(ValueError):
+            tokenizer.export_sentencepiece(io.BytesIO())
+
+
+if __name__ == "__main__":
+    unittest.main()
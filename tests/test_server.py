@@ -0,0 +1,72 @@
+import http.client
+import json
+import threading
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, make_server
+
+CORPUS = "\n".join(["the quick brown fox jumps over the lazy dog"] * 20)
+
+
+class ServerTest(unittest.TestCase):
+
+    @classmethod
+    def setUpClass(cls):
+        tokenizer = BPETokenizer()
+        tokenizer.train(CORPUS, 280)
+        cls.tokenizer = tokenizer
+        cls.server = make_server(tokenizer, port=0)
+        threading.Thread(target=cls.server.serve_forever, daemon=True).start()
+
+
+    @classmethod
+    def tearDownClass(cls):
+        cls.server.shutdown()
+        cls.server.server_close()
+
+
+    def post(self, path: str, body: bytes, headers: dict | None = None) -> tuple[int, dict]:
+        connection = http.client.HTTPConnection(*self.server.server_address, timeout=5)
+        try:
+            connection.putrequest("POST", path)
+            for name, value in (headers or {"Content-Length" : str(len(body))}).items():
+                connection.putheader(name, value)
+            connection.endheaders(body)
+            response = connection.getresponse()
+            return response.status, json.loads(response.read())
+        finally:
+            connection.close()
+
+
+    def test_encode_and_count_agree(self):
+        body = json.dumps({"text" : "the lazy fox"}).encode("utf-8")
+        status, encoded = self.post("/encode", body)
+        self.assertEqual(status, 200)
+        self.assertEqual(encoded["ids"], self.tokenizer.encode("the lazy fox"))
+        self.assertEqual(self.post("/count_tokens", body), (200, {"count" : len(encoded["ids"])}))
+
+
+    def test_count_tokens_honors_disallowed_special(self):
+        body = json.dumps({"text" : "the <|endoftext|>", "disallowed_special" : "all"}).encode("utf-8")
+        for path in ("/encode", "/count_tokens"):
+            with self.subTest(path=path):
+                status, response = self.post(path, body)
+                self.assertEqual(status, 400)
+                self.assertEqual(response["code"], "disallowed_special_token")
+
+
+    def test_invalid_content_length(self):
+        for value in ("abc", "-1"):
+            with self.subTest(value=value):
+                status, response = self.post("/encode", b"{}", {"Content-Length" : value})
+                self.assertEqual(status, 400)
+                self.assertIn("Content-Length", response["error"])
+
+
+    def test_malformed_body(self):
+        self.assertEqual(self.post("/encode", b"[1, 2]")[0], 400)
+        self.assertEqual(self.post("/decode", b"not json")[0], 400)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,64 @@
+import unittest
+
+from Lucid.Tokenizer import BPETokenizer, TokenizerConfig, WordBoundary
+
+CORPUS = "\n".join(["the café serves crème brûlée", "hello world", "foo bar baz"] * 20)
+
+
+def trained_tokenizer(config: TokenizerConfig) -> BPETokenizer:
+    tokenizer = BPETokenizer(config)
+    tokenizer.train(CORPUS, 300)
+    return tokenizer
+
+
+class StopMatcherTest(unittest.TestCase):
+
+    def check_against_decode(self, tokenizer: BPETokenizer, text: str, stop_sequences: list[str]) -> None:
+        # The hit found on token ids must be the first match in the fully decoded text
+        token_ids = tokenizer.encode(text)
+        decoded = tokenizer.decode(token_ids)
+        hit = tokenizer.find_stop(token_ids, stop_sequences)
+        expected = tokenizer.find_stop(decoded, stop_sequences)
+        self.assertIsNotNone(expected)
+        self.assertEqual((hit.stop_sequence, hit.start, hit.end), (expected.stop_sequence, expected.start, expected.end))
+        self.assertEqual(decoded[hit.start:hit.end], hit.stop_sequence)
+        # The completing token is the first one whose decoded prefix contains the match
+        self.assertIn(hit.stop_sequence, tokenizer.decode(token_ids[:hit.token_index + 1]))
+        self.assertNotIn(hit.stop_sequence, tokenizer.decode(token_ids[:hit.token_index]))
+
+
+    def test_character_split_across_byte_level_tokens(self):
+        tokenizer = trained_tokenizer(TokenizerConfig(byte_level=True))
+        self.check_against_decode(tokenizer, "café", ["é"])
+        self.check_against_decode(tokenizer, "the crème brûlée", ["û", "ée"])
+        self.assertIsNone(tokenizer.find_stop(tokenizer.encode("cafe"), ["é"]))
+
+
+    def test_offsets_follow_decode_spacing(self):
+        # Newlines are spaced out from the preceding word by decode
+        tokenizer = trained_tokenizer(TokenizerConfig())
+        self.check_against_decode(tokenizer, "hello world\nfoo", ["\nfoo", "foo"])
+        self.check_against_decode(tokenizer, "hello world\n\nfoo bar", ["bar"])
+
+
+    def test_end_of_word_boundary(self):
+        tokenizer = trained_tokenizer(TokenizerConfig(word_boundary=WordBoundary.END, space_marker=None))
+        self.check_against_decode(tokenizer, "hello world foo", ["world f"])
+
+
+    def test_stop_string_spanning_tokens(self):
+        tokenizer = trained_tokenizer(TokenizerConfig())
+        matcher = tokenizer.compile_stop_sequences(["d fo"])
+        hits = [matcher.feed(token_id) for token_id in tokenizer.encode("hello world foo bar")]
+        hit = next(hit for hit in hits if hit is not None)
+        self.assertEqual(hit.stop_sequence, "d fo")
+        self.assertEqual(hits.index(hit), hit.token_index)
+
+        # Reset starts over from an empty stream
+        matcher.reset()
+        self.assertIsNone(matcher.feed(tokenizer.encode("foo")[0]))
+        self.assertEqual(matcher.tokens_fed, 1)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,41 @@
+import unittest
+from unittest import mock
+
+from Lucid.Tokenizer import BPETokenizer, TrainerConfig, TrainingStats
+from Lucid.Tokenizer import parallel
+
+# Larger than PARALLEL_CHUNK_CHARS, so the corpus is split into several chunks
+CORPUS = "\n".join(["the quick brown fox jumps over the lazy dog", "hello world"] * 40000)
+
+
+class TrainingWorkersTest(unittest.TestCase):
+
+    def test_default_training_starts_no_process_pool(self):
+        # Scripts without a main-module guard must keep working under the spawn start method
+        self.assertEqual(parallel.get_num_threads(), 1)
+        with mock.patch.object(parallel, "ProcessPoolExecutor", side_effect=AssertionError("process pool started")):
+            BPETokenizer().train(CORPUS, 300)
+
+
+    def test_worker_pool_counts_match(self):
+        tokenizer = BPETokenizer()
+        single = tokenizer._count_training_words(CORPUS, TrainerConfig(), TrainingStats())
+        pooled = tokenizer._count_training_words(CORPUS, TrainerConfig(num_workers=2), TrainingStats())
+        self.assertEqual(pooled, single)
+
+
+    def test_worker_count_follows_environment(self):
+        with mock.patch.dict("os.environ", {"LUCID_NUM_THREADS" : "2"}):
+            self.assertEqual(parallel.get_num_threads(), 2)
+            with mock.patch.object(parallel, "ProcessPoolExecutor", wraps=parallel.ProcessPoolExecutor) as pool:
+                BPETokenizer()._count_training_words(CORPUS, TrainerConfig(), TrainingStats())
+        self.assertEqual(pool.call_count, 1)
+
+
+    def test_invalid_worker_count(self):
+        with self.assertRaises(ValueError):
+            TrainerConfig(num_workers=0)
+
+
+if __name__ == "__main__":
+    unittest.main()